@@ -16,12 +16,21 @@ TEMP_STEPS = 5
 CPU_COLORS = ["crimson", "darkorange", "salmon", "indianred", "orangered", "lightcoral"]
 GPU_COLORS = ["forestgreen", "limegreen", "darkgreen", "mediumseagreen", "springgreen", "seagreen"]
 OTHER_COLORS = ["dimgray", "darkgray", "slategray", "lightslategray", "silver", "gray"]
+FAN_COLORS = ["royalblue", "deepskyblue", "navy", "steelblue", "dodgerblue", "cadetblue"]
 SPIKE_COLORS = ["darkviolet", "mediumorchid", "indigo", "blueviolet", "purple", "darkmagenta"]
+UNPLOTTED_TYPES = {"POWER", "VOLT", "CURR", "COOLING", "FREQ", "UTIL", "LOAD", "PWM",
+                   "STATE", "CAPACITY", "THROTTLE", "PSI"}
+# Power-supply events: an AC adapter going on or off line.
+AC_EVENTS = {"plugged": "AC on", "unplugged": "AC off"}
+# Alarm flags raised or cleared on a channel.
+ALARM_EVENTS = {"alarm": "alarm", "alarm_cleared": "alarm cleared"}
+CORE_CMAP = "autumn"
+TRIP_COLORS = {"critical": "red", "hot": "orangered", "passive": "darkviolet", "active": "gray"}
 
 
 def parse_args():
     args = sys.argv[1:]
-    kwargs = {"paths": [], "max_temp": MAX_TEMP, "temp_steps": TEMP_STEPS}
+    kwargs = {"paths": [], "max_temp": MAX_TEMP, "temp_steps": TEMP_STEPS, "only": None}
     i = 0
     while i < len(args):
         if args[i] == "--max-temp" and i + 1 < len(args):
@@ -30,14 +39,29 @@ def parse_args():
         elif args[i] == "--temp-steps" and i + 1 < len(args):
             kwargs["temp_steps"] = int(args[i + 1])
             i += 2
+        elif args[i] == "--only" and i + 1 < len(args):
+            kwargs["only"] = set(filter(None, args[i + 1].split(",")))
+            i += 2
         else:
             kwargs["paths"].append(args[i])
             i += 1
     return kwargs
 
 
-def load_csv(path):
+def channel_starts(path):
+    """Frame each channel hotplugged mid-session first appeared in; one that
+    went offline first was recorded from the start."""
+    starts = {}
+    for frame, kind, sensor_id in load_events(path):
+        if kind not in ("appeared", "disappeared"):
+            continue
+        starts.setdefault(sensor_id, frame if kind == "appeared" else 0)
+    return starts
+
+
+def load_csv(path, only=None):
     series = defaultdict(list)
+    starts = channel_starts(path)
     with open(path) as f:
         for line in f:
             line = line.strip()
@@ -45,15 +69,139 @@ def load_csv(path):
                 continue
             parts = line.split(",")
             if len(parts) >= 3:
-                typ, label, temp = parts[0], parts[1], float(parts[2])
-                series[(typ, label)].append(temp)
+                # An empty value is a missing sample; NaN leaves a gap.
+                typ, sensor_id = parts[0], parts[1]
+                temp = float(parts[2]) if parts[2] else float("nan")
+                if typ in UNPLOTTED_TYPES or (only is not None and sensor_id not in only):
+                    continue
+                if (typ, sensor_id) not in series:
+                    # Keep a late channel aligned with the rest of the session.
+                    series[(typ, sensor_id)] = [float("nan")] * starts.get(sensor_id, 0)
+                series[(typ, sensor_id)].append(temp)
     return dict(series)
 
 
+def load_throttles(path):
+    """Frames in which any THROTTLE channel counted throttling events."""
+    starts = channel_starts(path)
+    rows = defaultdict(int)
+    frames = set()
+    with open(path) as f:
+        for line in f:
+            parts = line.strip().split(",")
+            if len(parts) < 3 or parts[0] != "THROTTLE":
+                continue
+            frame = starts.get(parts[1], 0) + rows[parts[1]]
+            rows[parts[1]] += 1
+            if parts[2] and float(parts[2]) > 0:
+                frames.add(frame)
+    return sorted(frames)
+
+
+def load_labels(path):
+    """Display labels by sensor ID from the session's # Sensor: header."""
+    labels = {}
+    with open(path) as f:
+        for line in f:
+            if not line.startswith("# Sensor:"):
+                continue
+            parts = line[len("# Sensor:"):].strip().split(",", 2)
+            if len(parts) == 3:
+                labels[parts[1]] = parts[2]
+    return labels
+
+
+def load_topology(path):
+    """CPU placement by sensor ID from the session's # Topology: header."""
+    topology = {}
+    with open(path) as f:
+        for line in f:
+            if not line.startswith("# Topology:"):
+                continue
+            parts = line[len("# Topology:"):].strip().split(",")
+            if len(parts) < 3:
+                continue
+            fields = dict(p.split("=", 1) for p in parts[2:] if "=" in p)
+            topology[parts[1]] = {
+                key: int(fields[key]) if key in fields else None
+                for key in ("package", "ccd", "core")
+            }
+    return topology
+
+
+def load_devices(path):
+    """Drive identity by sensor ID from the session's # Device: header."""
+    devices = {}
+    with open(path) as f:
+        for line in f:
+            if not line.startswith("# Device:"):
+                continue
+            parts = line[len("# Device:"):].strip().split(",")
+            if len(parts) >= 3:
+                devices[parts[1]] = dict(p.split("=", 1) for p in parts[2:] if "=" in p)
+    return devices
+
+
+def placement_key(place):
+    """Sort key grouping readings by package, then CCD, then core."""
+    if place is None:
+        return (0,)
+    return (1,) + tuple(-1 if place[k] is None else place[k] for k in ("package", "ccd", "core"))
+
+
+def is_local(place):
+    return place is not None and (place["ccd"] is not None or place["core"] is not None)
+
+
+def series_name(typ, sensor_id, labels, topology=None, devices=None):
+    label = labels.get(sensor_id)
+    name = f"{typ}.{label} [{sensor_id}]" if label else f"{typ}.{sensor_id}"
+    device = (devices or {}).get(sensor_id)
+    if device:
+        drive = device.get("model", device.get("name", ""))
+        if "model" in device and "name" in device:
+            drive += f" ({device['name']})"
+        name += f" — {drive}"
+    place = (topology or {}).get(sensor_id)
+    if place is not None:
+        where = [f"pkg {place['package']}"]
+        where += [f"{k} {place[k]}" for k in ("ccd", "core") if place[k] is not None]
+        name += f" ({' '.join(where)})"
+    return name
+
+
+def load_trips(path):
+    """Thermal trip points from the session header as (zone, type, temp)."""
+    trips = []
+    with open(path) as f:
+        for line in f:
+            if not line.startswith("# Trip:"):
+                continue
+            parts = line[len("# Trip:"):].strip().split(",")
+            if len(parts) >= 3:
+                trips.append((parts[0], parts[1], float(parts[2])))
+    return trips
+
+
+def load_events(path):
+    """Hotplug, AC and alarm events from # Event: lines as (frame, kind, id)."""
+    events = []
+    with open(path) as f:
+        for line in f:
+            if not line.startswith("# Event:"):
+                continue
+            parts = line[len("# Event:"):].strip().split(",", 2)
+            if len(parts) == 3:
+                events.append((int(parts[0]), parts[1], parts[2]))
+    return events
+
+
 def session_color(si, typ):
     """Distinct color per session per device type."""
     if typ == "CPU":
         return CPU_COLORS[si % len(CPU_COLORS)]
+    elif typ == "FAN":
+        return FAN_COLORS[si % len(FAN_COLORS)]
     elif typ == "GPU":
         return GPU_COLORS[si % len(GPU_COLORS)]
     else:
@@ -72,7 +220,7 @@ def find_heat_spikes(temps, margin_pct=10):
     return sorted(idx for idx, _ in positive[:n])
 
 
-def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS):
+def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS, only=None):
     if not paths:
         print("No session files provided")
         sys.exit(1)
@@ -84,22 +232,48 @@ def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS):
     title = f"Sessions [{suffix}]" if multi else os.path.basename(paths[0])
     fig.canvas.manager.set_window_title(f"Twatch — {title}")
 
-    all_series = [load_csv(p) for p in paths]
+    all_series = [load_csv(p, only) for p in paths]
+    all_labels = [load_labels(p) for p in paths]
+    all_topology = [load_topology(p) for p in paths]
+    all_devices = [load_devices(p) for p in paths]
     global_samples = max(
         max((len(v) for v in s.values()), default=0) for s in all_series
     )
     xs = list(range(global_samples))
+    has_fans = any(typ == "FAN" for data in all_series for typ, _ in data)
+    fan_ax = ax.twinx() if has_fans else None
 
+    hottest = None
     for si, data in enumerate(all_series):
-        for (typ, label), temps in data.items():
+        topology = all_topology[si]
+        ordered = sorted(data.items(), key=lambda kv: placement_key(topology.get(kv[0][1])))
+        local = [sid for (_, sid), _ in ordered if is_local(topology.get(sid))]
+        core_cmap = plt.get_cmap(CORE_CMAP)
+        for (typ, sensor_id), temps in ordered:
+            name = series_name(typ, sensor_id, all_labels[si], topology, all_devices[si])
+            if typ == "FAN":
+                lbl = f"S{si + 1} {name}" if multi else name
+                pad = [None] * (global_samples - len(temps))
+                fan_ax.plot(xs, temps + pad, color=session_color(si, typ),
+                            linestyle="--", linewidth=1.2, label=lbl)
+                continue
+
             c = session_color(si, typ)
+            if sensor_id in local and not multi:
+                # One shade per core or CCD, in topology order.
+                c = core_cmap(local.index(sensor_id) / max(len(local), 1) * 0.8)
+            valid = [t for t in temps if not np.isnan(t)]
+            if sensor_id in local and valid:
+                peak = max(valid)
+                if hottest is None or peak > hottest[1]:
+                    hottest = (f"S{si + 1} {name}" if multi else name, peak)
             alpha = 1.0 if typ in ("CPU", "GPU") else 0.3
             lw = 2.0 if typ in ("CPU", "GPU") else 1.0
 
             if multi:
-                lbl = f"S{si + 1} {typ}.{label}"
+                lbl = f"S{si + 1} {name}"
             else:
-                lbl = f"{typ}.{label}"
+                lbl = name
 
             pad = [None] * (global_samples - len(temps))
             y = temps + pad
@@ -115,6 +289,34 @@ def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS):
                                s=60, zorder=10, alpha=0.85, edgecolors="black",
                                linewidths=0.4)
 
+    trips = sorted({t for p in paths for t in load_trips(p)}, key=lambda t: t[2])
+    for zone, kind, temp in trips:
+        if temp > max_temp:
+            continue
+        color = TRIP_COLORS.get(kind, "gray")
+        ax.axhline(temp, color=color, linestyle=":", linewidth=1.0, alpha=0.7)
+        ax.annotate(f"{zone} {kind} {temp:g}°C", xy=(0, temp), xycoords=("axes fraction", "data"),
+                    xytext=(4, 2), textcoords="offset points", fontsize=6.5, color=color)
+
+    throttles = sorted({f for p in paths for f in load_throttles(p)})
+    for frame in throttles:
+        ax.axvspan(frame - 0.5, frame + 0.5, color="red", alpha=0.12, linewidth=0)
+
+    events = [(si, e) for si, p in enumerate(paths) for e in load_events(p)]
+    for si, (frame, kind, sensor_id) in events:
+        name = all_labels[si].get(sensor_id, sensor_id)
+        if kind in AC_EVENTS:
+            text, color = AC_EVENTS[kind], "darkorange"
+        elif kind in ALARM_EVENTS:
+            text, color = f"{ALARM_EVENTS[kind]} {name}", "red"
+        else:
+            text, color = f"{'+' if kind == 'appeared' else '−'}{name}", "slategray"
+        ax.axvline(frame, color=color, linestyle="-.", linewidth=0.8, alpha=0.6)
+        ax.annotate(f"{f'S{si + 1} ' if multi else ''}{text}", xy=(frame, 1),
+                    xycoords=("data", "axes fraction"), xytext=(2, -10),
+                    textcoords="offset points", fontsize=6.5, color=color, rotation=90,
+                    verticalalignment="top")
+
     ax.set_xlabel("Sample")
     ax.set_ylabel("Temperature (°C)")
     ax.set_title(title)
@@ -122,15 +324,37 @@ def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS):
     ax.yaxis.set_major_locator(mticker.MultipleLocator(temp_steps))
     ax.grid(True, alpha=0.3)
 
+    handles, labels = ax.get_legend_handles_labels()
+    if fan_ax is not None:
+        fan_ax.set_ylabel("Fan speed (RPM)")
+        fan_ax.set_ylim(bottom=0)
+        fan_handles, fan_labels = fan_ax.get_legend_handles_labels()
+        handles += fan_handles
+        labels += fan_labels
+
     if multi:
-        legend = ax.legend(fontsize=7, ncol=2, framealpha=0.9,
+        legend = ax.legend(handles, labels, fontsize=7, ncol=2, framealpha=0.9,
                            title="Session — Sensor")
         legend.get_title().set_fontsize(8)
     else:
-        ax.legend(fontsize=8)
+        ax.legend(handles, labels, fontsize=8)
 
     desc = ["▼ = heat spike (fastest 10% rise)"]
     desc.append("CPU = red tones  ·  GPU = green tones  ·  other = gray")
+    if has_fans:
+        desc.append("dashed blue = fan RPM (right axis)")
+    if trips:
+        desc.append("dotted = thermal trip points")
+    if throttles:
+        desc.append(f"red band = CPU throttled ({len(throttles)} samples)")
+    if any(kind in ("appeared", "disappeared") for _, (_, kind, _) in events):
+        desc.append("dash-dot = sensor appeared (+) or went offline (−)")
+    if any(kind in ALARM_EVENTS for _, (_, kind, _) in events):
+        desc.append("red dash-dot = driver alarm raised or cleared")
+    if any(kind in AC_EVENTS for _, (_, kind, _) in events):
+        desc.append("orange dash-dot = AC adapter plugged in or unplugged")
+    if hottest is not None:
+        desc.append(f"hot spot: {hottest[0]} peak {hottest[1]:g}°C")
     if multi:
         parts = []
         for si in range(len(paths)):
@@ -156,6 +380,6 @@ def plot(paths, max_temp=MAX_TEMP, temp_steps=TEMP_STEPS):
 if __name__ == "__main__":
     kwargs = parse_args()
     if not kwargs["paths"]:
-        print("Usage: plot.py [--max-temp N] [--temp-steps N] <session.csv> ...")
+        print("Usage: plot.py [--max-temp N] [--temp-steps N] [--only ID,...] <session.csv> ...")
         sys.exit(1)
-    plot(kwargs["paths"], kwargs["max_temp"], kwargs["temp_steps"])
+    plot(kwargs["paths"], kwargs["max_temp"], kwargs["temp_steps"], kwargs["only"])
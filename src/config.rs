@@ -0,0 +1,478 @@
+use crate::{
+    expr::Expr,
+    selector::Selector,
+    sensors::{Calibration, ChannelKind, DeviceKind, VirtualSensor},
+};
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Settings read from the twatch config file. The format is INI-like:
+///
+/// ```text
+/// keep_unclassified = true
+/// trigger = virtual/hottest_ccd
+/// nvidia_smi = /opt/nvidia/bin/nvidia-smi
+///
+/// [classify]
+/// nct6799 = board
+/// my_ec = battery
+///
+/// [sensor k10temp/0000:00:18.3/temp1]
+/// alias = CPU package
+/// offset = -10
+///
+/// [virtual hottest_ccd]
+/// expr = max({k10temp/*/temp3}, {k10temp/*/temp4})
+/// ```
+#[derive(Default)]
+pub struct FileConfig {
+    pub keep_unclassified: Option<bool>,
+    pub classify: Vec<(String, DeviceKind)>,
+    pub sensors: Vec<Calibration>,
+    pub virtuals: Vec<VirtualSensor>,
+    /// Channel driving `--by-temperature` and `temp`.
+    pub trigger: Option<Selector>,
+    pub nvidia_smi: Option<PathBuf>,
+}
+
+struct Section {
+    name: String,
+    /// Line of the header, 0 for the top level.
+    line: usize,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    key: String,
+    value: String,
+    line: usize,
+}
+
+/// `$XDG_CONFIG_HOME/twatch/twatch.conf`, falling back to `~/.config`.
+pub fn default_path() -> PathBuf {
+    config_path(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    )
+}
+
+/// The XDG base directory spec has unset, empty and relative values of
+/// `XDG_CONFIG_HOME` all fall back to `$HOME/.config`.
+fn config_path(config_home: Option<OsString>, home: Option<OsString>) -> PathBuf {
+    let config_home = config_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| PathBuf::from(home.unwrap_or_default()).join(".config"));
+    config_home.join("twatch").join("twatch.conf")
+}
+
+/// Loads `path`, or the default location when none is given. A missing
+/// default file is not an error; a missing explicit one is.
+pub fn load(path: Option<&Path>) -> io::Result<FileConfig> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => (default_path(), false),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+            return Ok(FileConfig::default())
+        }
+        Err(e) => return Err(with_path(e, &path)),
+    };
+
+    from_sections(&parse(&text).map_err(|e| with_path(e, &path))?).map_err(|e| with_path(e, &path))
+}
+
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
+
+fn invalid(line: usize, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+/// Splits the file into sections. Keys before the first header belong to a
+/// section with an empty name.
+fn parse(text: &str) -> io::Result<Vec<Section>> {
+    let mut sections = vec![Section {
+        name: String::new(),
+        line: 0,
+        entries: Vec::new(),
+    }];
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| invalid(line, format!("unterminated section header '{}'", raw)))?
+                .trim();
+            sections.push(Section {
+                name: header.to_lowercase(),
+                line,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| invalid(line, format!("expected 'key = value', got '{}'", raw)))?;
+        if let Some(section) = sections.last_mut() {
+            section.entries.push(Entry {
+                key: key.trim().to_string(),
+                value: unquote(value.trim()).to_string(),
+                line,
+            });
+        }
+    }
+
+    Ok(sections)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn parse_bool(entry: &Entry) -> io::Result<bool> {
+    match entry.value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(invalid(
+            entry.line,
+            format!("'{}' expects true or false", entry.key),
+        )),
+    }
+}
+
+fn parse_number(entry: &Entry) -> io::Result<f64> {
+    entry
+        .value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| {
+            invalid(
+                entry.line,
+                format!("'{}' expects a number, got '{}'", entry.key, entry.value),
+            )
+        })
+}
+
+/// `[sensor <id>]`: the ID may be a glob such as `nct6798/*/temp7`.
+fn sensor_section(pattern: &str, entries: &[Entry]) -> io::Result<Calibration> {
+    let mut calibration = Calibration {
+        pattern: pattern.to_string(),
+        alias: None,
+        offset: 0.0,
+        scale: 1.0,
+        ignore: false,
+    };
+    for entry in entries {
+        match entry.key.as_str() {
+            "alias" | "label" => calibration.alias = Some(entry.value.clone()),
+            "offset" => calibration.offset = parse_number(entry)?,
+            "scale" => calibration.scale = parse_number(entry)?,
+            "ignore" => calibration.ignore = parse_bool(entry)?,
+            key => {
+                return Err(invalid(
+                    entry.line,
+                    format!("unknown key '{}' in [sensor {}]", key, pattern),
+                ))
+            }
+        }
+    }
+    Ok(calibration)
+}
+
+/// `[virtual <name>]`: a channel computed from others by `expr`.
+fn virtual_section(name: &str, entries: &[Entry], line: usize) -> io::Result<VirtualSensor> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+    {
+        return Err(invalid(
+            line,
+            format!(
+                "virtual sensor name '{}' may only use letters, digits, '_', '-' and '.'",
+                name
+            ),
+        ));
+    }
+
+    let mut expr = None;
+    let mut label = None;
+    let mut kind = ChannelKind::Temperature;
+    let mut device = None;
+    for entry in entries {
+        match entry.key.as_str() {
+            "expr" => {
+                expr = Some(Expr::parse(&entry.value).map_err(|e| invalid(entry.line, e))?);
+            }
+            "label" | "alias" => label = Some(entry.value.clone()),
+            "kind" => {
+                kind = ChannelKind::from_name(&entry.value).ok_or_else(|| {
+                    invalid(
+                        entry.line,
+                        format!("unknown channel kind '{}'", entry.value),
+                    )
+                })?;
+            }
+            "device" => {
+                device = Some(DeviceKind::from_name(&entry.value).ok_or_else(|| {
+                    invalid(entry.line, format!("unknown device kind '{}'", entry.value))
+                })?);
+            }
+            key => {
+                return Err(invalid(
+                    entry.line,
+                    format!("unknown key '{}' in [virtual {}]", key, name),
+                ))
+            }
+        }
+    }
+
+    Ok(VirtualSensor {
+        name: name.to_string(),
+        label,
+        kind,
+        device,
+        expr: expr.ok_or_else(|| invalid(line, format!("[virtual {}] needs an expr", name)))?,
+    })
+}
+
+fn from_sections(sections: &[Section]) -> io::Result<FileConfig> {
+    let mut config = FileConfig::default();
+
+    for section in sections {
+        if let Some(pattern) = section.name.strip_prefix("sensor ") {
+            config
+                .sensors
+                .push(sensor_section(pattern.trim(), &section.entries)?);
+            continue;
+        }
+        if let Some(name) = section.name.strip_prefix("virtual ") {
+            config.virtuals.push(virtual_section(
+                name.trim(),
+                &section.entries,
+                section.line,
+            )?);
+            continue;
+        }
+        for entry in &section.entries {
+            match (section.name.as_str(), entry.key.as_str()) {
+                ("", "keep_unclassified") => config.keep_unclassified = Some(parse_bool(entry)?),
+                ("", "nvidia_smi") => config.nvidia_smi = Some(PathBuf::from(&entry.value)),
+                ("", "trigger") => {
+                    config.trigger = Some(
+                        entry
+                            .value
+                            .parse()
+                            .map_err(|e| invalid(entry.line, format!("trigger: {}", e)))?,
+                    );
+                }
+                ("classify", chip) => {
+                    let kind = DeviceKind::from_name(&entry.value).ok_or_else(|| {
+                        invalid(
+                            entry.line,
+                            format!("unknown device kind '{}' for '{}'", entry.value, chip),
+                        )
+                    })?;
+                    config.classify.push((chip.to_string(), kind));
+                }
+                (name, key) => {
+                    let place = if name.is_empty() {
+                        "top level".to_string()
+                    } else {
+                        format!("[{}]", name)
+                    };
+                    return Err(invalid(
+                        entry.line,
+                        format!("unknown key '{}' in {}", key, place),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{config_path, from_sections, parse, FileConfig};
+    use crate::sensors::DeviceKind;
+    use std::path::PathBuf;
+
+    fn load(text: &str) -> Result<FileConfig, String> {
+        parse(text)
+            .and_then(|sections| from_sections(&sections))
+            .map_err(|e| e.to_string())
+    }
+
+    fn error(text: &str) -> String {
+        load(text).err().expect("config should be rejected")
+    }
+
+    #[test]
+    fn default_path_follows_xdg() {
+        let path = |config_home: Option<&str>| {
+            config_path(config_home.map(Into::into), Some("/home/ana".into()))
+        };
+        let fallback = PathBuf::from("/home/ana/.config/twatch/twatch.conf");
+        assert_eq!(
+            path(Some("/etc/xdg")),
+            PathBuf::from("/etc/xdg/twatch/twatch.conf")
+        );
+        assert_eq!(path(None), fallback);
+        assert_eq!(path(Some("")), fallback);
+        assert_eq!(path(Some("config")), fallback);
+    }
+
+    #[test]
+    fn reads_sections() {
+        let config = load(
+            "# twatch\n\
+             keep_unclassified = yes\n\
+             nvidia_smi = \"/opt/nvidia/bin/nvidia-smi\"\n\
+             trigger = chip:k10temp\n\
+             \n\
+             [classify]\n\
+             ; board controllers\n\
+             nct6799 = board\n\
+             \n\
+             [ sensor nct6798/*/temp7 ]\n\
+             alias = \" CPU socket \"\n\
+             offset = -2.5\n\
+             scale = 1.1\n\
+             \n\
+             [sensor acpitz/*]\n\
+             ignore = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.keep_unclassified, Some(true));
+        assert_eq!(
+            config.nvidia_smi,
+            Some(PathBuf::from("/opt/nvidia/bin/nvidia-smi"))
+        );
+        assert_eq!(config.trigger.unwrap().to_string(), "chip:k10temp");
+        assert_eq!(config.classify.len(), 1);
+        assert!(config.classify[0].0 == "nct6799" && config.classify[0].1 == DeviceKind::Board);
+
+        let socket = &config.sensors[0];
+        assert_eq!(socket.pattern, "nct6798/*/temp7");
+        // Quotes keep the spaces inside them.
+        assert_eq!(socket.alias.as_deref(), Some(" CPU socket "));
+        assert_eq!(
+            (socket.offset, socket.scale, socket.ignore),
+            (-2.5, 1.1, false)
+        );
+        let acpitz = &config.sensors[1];
+        assert_eq!(
+            (acpitz.offset, acpitz.scale, acpitz.ignore),
+            (0.0, 1.0, true)
+        );
+    }
+
+    #[test]
+    fn unbalanced_quotes_stay() {
+        let config = load("[sensor nvme/*]\nalias = \"Samsung 980\n").unwrap();
+        assert_eq!(config.sensors[0].alias.as_deref(), Some("\"Samsung 980"));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert_eq!(
+            error("\n[sensor k10temp/*\n"),
+            "line 2: unterminated section header '[sensor k10temp/*'"
+        );
+        assert_eq!(
+            error("keep_unclassified\n"),
+            "line 1: expected 'key = value', got 'keep_unclassified'"
+        );
+        assert_eq!(
+            error("colour = red\n"),
+            "line 1: unknown key 'colour' in top level"
+        );
+        assert_eq!(
+            error("[display]\nwidth = 80\n"),
+            "line 2: unknown key 'width' in [display]"
+        );
+        assert_eq!(
+            error("keep_unclassified = maybe\n"),
+            "line 1: 'keep_unclassified' expects true or false"
+        );
+        assert_eq!(
+            error("[classify]\nmy_ec = toaster\n"),
+            "line 2: unknown device kind 'toaster' for 'my_ec'"
+        );
+        assert_eq!(
+            error("trigger = kind:warp\n").split(" (").next(),
+            Some("line 1: trigger: unknown kind 'warp'")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_sensor_sections() {
+        assert_eq!(
+            error("[sensor k10temp/*]\noffset = ten\n"),
+            "line 2: 'offset' expects a number, got 'ten'"
+        );
+        assert_eq!(
+            error("[sensor k10temp/*]\nscale = inf\n"),
+            "line 2: 'scale' expects a number, got 'inf'"
+        );
+        assert_eq!(
+            error("[sensor k10temp/*]\nhide = true\n"),
+            "line 2: unknown key 'hide' in [sensor k10temp/*]"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_virtual_sections() {
+        assert_eq!(
+            error("[virtual hot ccd]\nexpr = 1\n"),
+            "line 1: virtual sensor name 'hot ccd' may only use letters, digits, '_', '-' and '.'"
+        );
+        assert_eq!(
+            error("[virtual ]\nexpr = 1\n"),
+            "line 2: unknown key 'expr' in [virtual]"
+        );
+        assert_eq!(
+            error("\n[virtual hottest]\nlabel = Hottest\n"),
+            "line 2: [virtual hottest] needs an expr"
+        );
+        assert_eq!(
+            error("[virtual hottest]\nexpr = 1\nkind = warp\n"),
+            "line 3: unknown channel kind 'warp'"
+        );
+        assert_eq!(
+            error("[virtual hottest]\nexpr = 1\ndevice = toaster\n"),
+            "line 3: unknown device kind 'toaster'"
+        );
+        assert_eq!(
+            error("[virtual hottest]\nexpr = 1\nunit = C\n"),
+            "line 3: unknown key 'unit' in [virtual hottest]"
+        );
+        assert!(error("[virtual hottest]\nexpr = max(\n").starts_with("line 2: "));
+
+        let config = load("[virtual hottest]\nexpr = max({k10temp/*/temp3}, 40)\n").unwrap();
+        assert_eq!(config.virtuals[0].name, "hottest");
+    }
+}
@@ -0,0 +1,56 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Failures that end a twatch command. A single sensor failing to read is
+/// not one of them: it is recorded as a missing sample instead.
+#[derive(Debug)]
+pub enum Error {
+    /// The config file could not be read or parsed.
+    Config(io::Error),
+    /// A `--sensor`/`--exclude` selector matched no channel.
+    Selector(String),
+    /// A sensor tree could not be listed at all.
+    Discovery { path: PathBuf, source: io::Error },
+    /// A session file could not be created, written or read back.
+    Session(io::Error),
+    /// The terminal could not be drawn to or read from.
+    Terminal(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Process exit code, following the BSD sysexits values.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config(_) => 78,
+            Error::Selector(_) => 64,
+            Error::Discovery { .. } => 69,
+            Error::Session(_) => 74,
+            Error::Terminal(_) => 71,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(e) => write!(f, "invalid config: {}", e),
+            Error::Selector(message) => write!(f, "selector {}", message),
+            Error::Discovery { path, source } => {
+                write!(f, "cannot list sensors in {}: {}", path.display(), source)
+            }
+            Error::Session(e) => write!(f, "session file error: {}", e),
+            Error::Terminal(e) => write!(f, "terminal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Config(e) | Error::Session(e) | Error::Terminal(e) => Some(e),
+            Error::Discovery { source, .. } => Some(source),
+            Error::Selector(_) => None,
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt;
+
+/// Arithmetic over sensor values, as written in lm-sensors `compute`
+/// statements: numbers, `@` (the raw value), variable names, `+ - * /`,
+/// parentheses, and the prefix operators `^` (e^x) and `` ` `` (ln x).
+/// `{...}` quotes a variable whose name contains other characters.
+/// `max`, `min`, `avg` and `sum` take any number of arguments; inside them a
+/// name with `*` or `?` stands for every variable it matches.
+#[derive(Clone)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Exp(Box<Expr>),
+    Ln(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Copy)]
+pub enum Func {
+    Max,
+    Min,
+    Avg,
+    Sum,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "max" => Some(Func::Max),
+            "min" => Some(Func::Min),
+            "avg" => Some(Func::Avg),
+            "sum" => Some(Func::Sum),
+            _ => None,
+        }
+    }
+}
+
+fn is_glob(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}' in '{}'", token, text)),
+        }
+    }
+
+    /// Evaluates with `var` resolving names (`@` included). `None` when a
+    /// name is unknown or the result is not a finite number.
+    pub fn eval(&self, var: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+        self.eval_with(var, &|name| var(name).into_iter().collect())
+    }
+
+    /// Like `eval`, with `expand` resolving the globs given to functions.
+    /// Functions skip arguments without a value, and have none when no
+    /// argument does.
+    pub fn eval_with(
+        &self,
+        var: &dyn Fn(&str) -> Option<f64>,
+        expand: &dyn Fn(&str) -> Vec<f64>,
+    ) -> Option<f64> {
+        let value = match self {
+            Expr::Number(n) => *n,
+            Expr::Var(name) => var(name)?,
+            Expr::Neg(inner) => -inner.eval_with(var, expand)?,
+            Expr::Exp(inner) => inner.eval_with(var, expand)?.exp(),
+            Expr::Ln(inner) => inner.eval_with(var, expand)?.ln(),
+            Expr::Call(func, args) => {
+                let values: Vec<f64> = args
+                    .iter()
+                    .flat_map(|arg| match arg {
+                        Expr::Var(name) if is_glob(name) => expand(name),
+                        arg => arg.eval_with(var, expand).into_iter().collect(),
+                    })
+                    .collect();
+                if values.is_empty() {
+                    return None;
+                }
+                match func {
+                    Func::Max => values.iter().copied().fold(f64::MIN, f64::max),
+                    Func::Min => values.iter().copied().fold(f64::MAX, f64::min),
+                    Func::Avg => values.iter().sum::<f64>() / values.len() as f64,
+                    Func::Sum => values.iter().sum(),
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (left.eval_with(var, expand)?, right.eval_with(var, expand)?);
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                }
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+
+    /// Every variable name the expression reads, globs included.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Var(name) => vec![name.as_str()],
+            Expr::Neg(inner) | Expr::Exp(inner) | Expr::Ln(inner) => inner.names(),
+            Expr::Binary(_, left, right) => {
+                let mut names = left.names();
+                names.extend(right.names());
+                names
+            }
+            Expr::Call(_, args) => args.iter().flat_map(Expr::names).collect(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                if !(d.is_ascii_digit() || d == '.') {
+                    break;
+                }
+                number.push(d);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("bad number '{}'", number))?;
+            tokens.push(Token::Number(value));
+        } else if c == '@' {
+            chars.next();
+            tokens.push(Token::Name("@".to_string()));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&d) = chars.peek() {
+                if !(d.is_ascii_alphanumeric() || d == '_') {
+                    break;
+                }
+                name.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if c == '{' {
+            chars.next();
+            let name: String = chars.by_ref().take_while(|&d| d != '}').collect();
+            tokens.push(Token::Name(name.trim().to_string()));
+        } else if "+-*/^`(),".contains(c) {
+            chars.next();
+            tokens.push(Token::Symbol(c));
+        } else {
+            return Err(format!("unexpected '{}' in '{}'", c, text));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, symbol: char) -> bool {
+        if self.tokens.get(self.pos) == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.next_if('+') {
+                Op::Add
+            } else if self.next_if('-') {
+                Op::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.next_if('*') {
+                Op::Mul
+            } else if self.next_if('/') {
+                Op::Div
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.next_if('-') {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else if self.next_if('^') {
+            Ok(Expr::Exp(Box::new(self.unary()?)))
+        } else if self.next_if('`') {
+            Ok(Expr::Ln(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Name(name)) if self.next_if('(') => {
+                let func =
+                    Func::from_name(&name).ok_or_else(|| format!("unknown function '{}'", name))?;
+                let mut args = vec![self.sum()?];
+                while self.next_if(',') {
+                    args.push(self.sum()?);
+                }
+                if self.next_if(')') {
+                    Ok(Expr::Call(func, args))
+                } else {
+                    Err(format!("missing ')' after {}(...", name))
+                }
+            }
+            Some(Token::Name(name)) => Ok(Expr::Var(name)),
+            Some(Token::Symbol('(')) => {
+                let expr = self.sum()?;
+                if self.next_if(')') {
+                    Ok(expr)
+                } else {
+                    Err("missing ')'".to_string())
+                }
+            }
+            Some(token) => Err(format!("unexpected '{}'", token)),
+            None => Err("expression ends too early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expr;
+
+    fn eval(text: &str, raw: f64) -> Option<f64> {
+        Expr::parse(text)
+            .unwrap()
+            .eval(&|name| (name == "@").then_some(raw))
+    }
+
+    #[test]
+    fn raw_value_arithmetic() {
+        assert_eq!(eval("@", 42.0), Some(42.0));
+        assert_eq!(eval("@-10", 50.0), Some(40.0));
+        assert_eq!(eval("@*(1+10/10)", 1.5), Some(3.0));
+        assert_eq!(eval("(@ + 2) * 3 - 4 / 2", 1.0), Some(7.0));
+        assert_eq!(eval("-@", 5.0), Some(-5.0));
+        assert_eq!(eval("2 - -@", 1.0), Some(3.0));
+    }
+
+    #[test]
+    fn exp_and_ln() {
+        assert_eq!(eval("^0", 0.0), Some(1.0));
+        assert_eq!(eval("`1", 0.0), Some(0.0));
+        let round_trip = eval("`^@", 2.5).unwrap();
+        assert!((round_trip - 2.5).abs() < 1e-12);
+        // ln of a negative number is not a reading.
+        assert_eq!(eval("`@", -1.0), None);
+    }
+
+    #[test]
+    fn non_finite_results_are_missing() {
+        assert_eq!(eval("@/0", 1.0), None);
+    }
+
+    #[test]
+    fn unknown_variables_are_missing() {
+        assert_eq!(eval("temp2 + @", 1.0), None);
+    }
+
+    #[test]
+    fn variables_and_functions() {
+        let expr = Expr::parse("max({k10temp/*/temp*}) - min(a, b, 3)").unwrap();
+        let value = expr.eval_with(
+            &|name| match name {
+                "a" => Some(10.0),
+                "b" => Some(4.0),
+                _ => None,
+            },
+            &|pattern| {
+                assert_eq!(pattern, "k10temp/*/temp*");
+                vec![50.0, 70.0, 60.0]
+            },
+        );
+        assert_eq!(value, Some(67.0));
+        assert_eq!(expr.names(), ["k10temp/*/temp*", "a", "b"]);
+    }
+
+    #[test]
+    fn functions_skip_missing_arguments() {
+        let expr = Expr::parse("avg(a, b, c)").unwrap();
+        let var = |name: &str| (name != "b").then_some(if name == "a" { 2.0 } else { 4.0 });
+        assert_eq!(expr.eval_with(&var, &|_| Vec::new()), Some(3.0));
+        let none = Expr::parse("sum(x, y)").unwrap();
+        assert_eq!(none.eval(&|_| None), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (text, message) in [
+            ("@ +", "expression ends too early"),
+            ("(@ + 1", "missing ')'"),
+            ("@ 1", "unexpected '1' in '@ 1'"),
+            ("@ % 2", "unexpected '%' in '@ % 2'"),
+            ("median(@)", "unknown function 'median'"),
+            ("max(@, 1", "missing ')' after max(..."),
+            ("1.2.3", "bad number '1.2.3'"),
+        ] {
+            match Expr::parse(text) {
+                Ok(_) => panic!("'{}' parsed", text),
+                Err(error) => assert_eq!(error, message, "for '{}'", text),
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    fs,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// A throwaway directory tree standing in for `/sys`, `/proc` or `/etc` in
+/// tests; removed when dropped.
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "twatch-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&root).unwrap();
+        Fixture { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Writes `contents` to `path` under the root, creating directories.
+    pub fn write(&self, path: &str, contents: &str) -> &Self {
+        let file = self.root.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, contents).unwrap();
+        self
+    }
+
+    /// Links `link` to `target`, both under the root, as sysfs links
+    /// class entries to their devices.
+    pub fn link(&self, link: &str, target: &str) -> &Self {
+        let link = self.root.join(link);
+        let target = self.root.join(target);
+        fs::create_dir_all(link.parent().unwrap()).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        symlink(target, link).unwrap();
+        self
+    }
+
+    pub fn remove(&self, path: &str) {
+        let path = self.root.join(path);
+        if path.is_dir() && !path.is_symlink() {
+            fs::remove_dir_all(path).unwrap();
+        } else {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
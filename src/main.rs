@@ -1,11 +1,26 @@
+mod config;
+mod error;
+mod expr;
+#[cfg(test)]
+mod fixture;
 mod plot;
+mod selector;
 mod sensors;
 mod session;
 
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
-use sensors::search_sensors;
-use session::{list_sessions, run_session};
-use std::{io, process};
+use error::Error;
+use selector::{Selection, Selector};
+use sensors::{
+    device_type, thresholds, Calibration, Classifier, LmSensorsConfig, MultiSource, SensorLabel,
+    SensorSource, VirtualSensor,
+};
+use session::{list_sessions, run_session, RunOptions};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    process,
+};
 
 #[derive(Parser)]
 #[command(name = "twatch", about = "Temperature monitoring and graphing tool")]
@@ -44,6 +59,93 @@ struct Cli {
         help = "Grid step interval (°C) on the plot Y-axis"
     )]
     temp_steps: u16,
+
+    #[arg(
+        long = "sysfs-root",
+        global = true,
+        help = "Read sensors from this sysfs tree instead of /sys (env: TWATCH_SYSFS_ROOT)"
+    )]
+    sysfs_root: Option<PathBuf>,
+
+    #[arg(
+        long = "procfs-root",
+        global = true,
+        help = "Read CPU load from this procfs tree instead of /proc (env: TWATCH_PROCFS_ROOT)"
+    )]
+    procfs_root: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Config file (default: ~/.config/twatch/twatch.conf)"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long = "lm-sensors",
+        global = true,
+        value_name = "PATH",
+        help = "lm-sensors config file or directory (default: /etc/sensors3.conf and /etc/sensors.d)"
+    )]
+    lm_sensors: Vec<PathBuf>,
+
+    #[arg(
+        long = "no-lm-sensors",
+        global = true,
+        conflicts_with = "lm_sensors",
+        help = "Ignore lm-sensors label, ignore and compute statements"
+    )]
+    no_lm_sensors: bool,
+
+    #[arg(
+        long = "nvidia-smi",
+        global = true,
+        value_name = "PATH",
+        help = "nvidia-smi binary queried for NVIDIA GPUs (default: nvidia-smi on PATH, none with --sysfs-root)"
+    )]
+    nvidia_smi: Option<PathBuf>,
+
+    #[arg(
+        long = "keep-unclassified",
+        global = true,
+        help = "Keep temperatures from drivers twatch cannot classify"
+    )]
+    keep_unclassified: bool,
+
+    #[arg(
+        long = "sensor",
+        global = true,
+        value_delimiter = ',',
+        value_name = "SELECTOR",
+        help = "Only these channels: kind:<kind>, chip:<glob>, label:<glob> or id:<glob>"
+    )]
+    sensor: Vec<Selector>,
+
+    #[arg(
+        long = "exclude",
+        global = true,
+        value_delimiter = ',',
+        value_name = "SELECTOR",
+        help = "Leave out these channels (same forms as --sensor)"
+    )]
+    exclude: Vec<Selector>,
+
+    #[arg(
+        long = "trigger",
+        global = true,
+        value_name = "SELECTOR",
+        help = "Temperature that drives --by-temperature and temp, e.g. virtual/hottest_ccd (default: first CPU temperature)"
+    )]
+    trigger: Option<Selector>,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value = "1",
+        global = true,
+        help = "Decimal places written for temperatures"
+    )]
+    precision: usize,
 }
 
 #[derive(Subcommand)]
@@ -66,7 +168,7 @@ enum Commands {
             default_value = "40",
             help = "Start temperature (°C) for --by-temperature mode"
         )]
-        initial_temp: u32,
+        initial_temp: f64,
 
         #[arg(
             short = 'e',
@@ -74,14 +176,7 @@ enum Commands {
             default_value = "70",
             help = "Stop temperature (°C) for --by-temperature mode"
         )]
-        end_temp: u32,
-
-        #[arg(
-            long,
-            default_value = "cpu",
-            help = "Target sensor for --by-temperature mode: cpu, gpu, or nvme"
-        )]
-        sensor: String,
+        end_temp: f64,
 
         #[arg(long, help = "Output JSON records to stdout instead of TUI")]
         json: bool,
@@ -118,29 +213,43 @@ pub struct Config {
     pub no_graph: bool,
     pub max_plot_temp: u16,
     pub temp_steps: u16,
+    pub sysfs_root: PathBuf,
+    pub procfs_root: PathBuf,
+    pub classifier: Classifier,
+    pub keep_unclassified: bool,
+    pub calibrations: Vec<Calibration>,
+    pub virtuals: Vec<VirtualSensor>,
+    pub trigger: Option<Selector>,
+    pub lm_sensors: LmSensorsConfig,
+    /// `None` leaves NVIDIA GPUs out.
+    pub nvidia_smi: Option<PathBuf>,
+    pub selection: Selection,
+    pub precision: usize,
 }
 
 fn main() {
-    let cli = Cli::parse();
-
-    let config = Config {
-        delay: cli.delay,
-        no_graph: cli.no_graph,
-        max_plot_temp: cli.max_plot_temp,
-        temp_steps: cli.temp_steps,
-    };
+    let mut cli = Cli::parse();
 
-    match cli.command.unwrap_or(Commands::List) {
+    match cli.command.take().unwrap_or(Commands::List) {
         Commands::List => print_sessions(),
 
         Commands::Temp => {
-            let sensors = search_sensors().expect("Unable to receive sensors information");
-            let cpu_temp = sensors
-                .iter()
-                .find(|s| s.is_cpu)
-                .map(|s| s.temp)
-                .unwrap_or(0);
-            println!("CPU TEMP: {}°C", cpu_temp);
+            let config = load_config(cli);
+            let mut source = MultiSource::system(&config);
+            let sensors = source.read_sensors().unwrap_or_else(|e| exit_with(e));
+            let target = session::target_sensor(&sensors, config.trigger.as_ref())
+                .unwrap_or_else(|e| exit_with(e));
+            match target {
+                Some(sensor) => println!(
+                    "{} TEMP: {}",
+                    device_type(sensor),
+                    describe_temp(sensor, config.precision)
+                ),
+                None => exit_with(Error::Discovery {
+                    path: config.sysfs_root,
+                    source: io::Error::new(io::ErrorKind::NotFound, "no temperature sensor found"),
+                }),
+            }
         }
 
         Commands::Run {
@@ -148,36 +257,41 @@ fn main() {
             count,
             initial_temp,
             end_temp,
-            sensor,
             json,
         } => {
             let capture_limit = count.unwrap_or(250);
+            let config = load_config(cli);
+            let mut source = MultiSource::system(&config);
 
-            run_session(
+            if let Err(e) = run_session(
                 &config,
-                by_temperature,
-                capture_limit,
-                initial_temp,
-                end_temp,
-                &sensor,
-                json,
-            )
-            .expect("Session failed");
+                &mut source,
+                RunOptions {
+                    by_temperature,
+                    capture_limit,
+                    initial_temp,
+                    end_temp,
+                    json_output: json,
+                },
+            ) {
+                exit_with(e);
+            }
         }
 
         Commands::Graph { session_ids } => {
-            let session_exists = list_sessions().map(|s| !s.is_empty()).unwrap_or(false);
-            if !session_exists {
-                eprintln!("No sessions available to plot.");
-                process::exit(1);
-            }
-            plot::plot_maker(
+            if let Err(e) = plot::plot_maker(
                 &session_ids,
                 plot::ScalingPlot {
-                    max_plot_temperature: config.max_plot_temp,
-                    number_of_steps_for_graph: config.temp_steps,
+                    max_plot_temperature: cli.max_plot_temp,
+                    number_of_steps_for_graph: cli.temp_steps,
+                },
+                &Selection {
+                    include: cli.sensor,
+                    exclude: cli.exclude,
                 },
-            );
+            ) {
+                exit_with(e);
+            }
         }
 
         Commands::Completions { shell } => {
@@ -193,6 +307,64 @@ fn main() {
     }
 }
 
+/// Reads the twatch and lm-sensors config files for the commands that
+/// sample sensors. A broken file named on the command line is an error;
+/// the system lm-sensors files are only warned about, as `sensors` does.
+fn load_config(cli: Cli) -> Config {
+    let file_config =
+        config::load(cli.config.as_deref()).unwrap_or_else(|e| exit_with(Error::Config(e)));
+
+    let lm_sensors = if cli.no_lm_sensors {
+        LmSensorsConfig::default()
+    } else if cli.lm_sensors.is_empty() {
+        LmSensorsConfig::load(&LmSensorsConfig::default_paths(), false).unwrap_or_else(|e| {
+            eprintln!("twatch: ignoring lm-sensors config: {}", e);
+            LmSensorsConfig::default()
+        })
+    } else {
+        LmSensorsConfig::load(&cli.lm_sensors, true).unwrap_or_else(|e| exit_with(Error::Config(e)))
+    };
+
+    let sysfs_root = cli
+        .sysfs_root
+        .or_else(|| env::var_os("TWATCH_SYSFS_ROOT").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("/sys"));
+    // A replayed sysfs tree is not this machine's GPUs.
+    let nvidia_smi = cli
+        .nvidia_smi
+        .or(file_config.nvidia_smi)
+        .or_else(|| (sysfs_root == Path::new("/sys")).then(|| PathBuf::from("nvidia-smi")));
+    Config {
+        delay: cli.delay,
+        no_graph: cli.no_graph,
+        max_plot_temp: cli.max_plot_temp,
+        temp_steps: cli.temp_steps,
+        sysfs_root,
+        procfs_root: cli
+            .procfs_root
+            .or_else(|| env::var_os("TWATCH_PROCFS_ROOT").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("/proc")),
+        classifier: Classifier::new(file_config.classify),
+        keep_unclassified: cli.keep_unclassified || file_config.keep_unclassified.unwrap_or(false),
+        calibrations: file_config.sensors,
+        virtuals: file_config.virtuals,
+        trigger: cli.trigger.or(file_config.trigger),
+        lm_sensors,
+        nvidia_smi,
+        selection: Selection {
+            include: cli.sensor,
+            exclude: cli.exclude,
+        },
+        precision: cli.precision,
+    }
+}
+
+/// Reports a failed command and exits with the error's code.
+fn exit_with(error: Error) -> ! {
+    eprintln!("twatch: {}", error);
+    process::exit(error.exit_code());
+}
+
 fn print_sessions() {
     match list_sessions() {
         Ok(sessions) => {
@@ -208,3 +380,25 @@ fn print_sessions() {
         Err(e) => eprintln!("Error listing sessions: {}", e),
     }
 }
+
+fn describe_temp(sensor: &SensorLabel, precision: usize) -> String {
+    let Some(value) = sensor.value else {
+        return "unavailable (sensor did not respond)".to_string();
+    };
+    let mut details: Vec<String> = thresholds(sensor)
+        .into_iter()
+        .map(|(name, value)| format!("{} {:.*}°C", name, precision, value))
+        .collect();
+    if let Some(crit) = sensor.crit {
+        details.push(format!("{:.*}°C to crit", precision, crit - value));
+    }
+    if sensor.alarm == Some(true) {
+        details.push("ALARM".to_string());
+    }
+
+    if details.is_empty() {
+        format!("{:.*}°C", precision, value)
+    } else {
+        format!("{:.*}°C ({})", precision, value, details.join(", "))
+    }
+}
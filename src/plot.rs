@@ -3,24 +3,44 @@ use std::process::Command;
 
 use std::{fs, io};
 
+use crate::error::{self, Error};
+use crate::selector::{Candidate, Selection};
+use crate::sensors::kinds_from_tag;
+
 #[derive(Clone, Copy)]
 pub struct ScalingPlot {
     pub max_plot_temperature: u16,
     pub number_of_steps_for_graph: u16,
 }
 
-pub fn plot_maker(session_ids: &[u16], scale: ScalingPlot) {
+pub fn plot_maker(
+    session_ids: &[u16],
+    scale: ScalingPlot,
+    selection: &Selection,
+) -> error::Result<()> {
     let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
     let session_dir = home.join("Documents").join("Twatch").join("session");
 
     let paths: Vec<PathBuf> = if session_ids.is_empty() {
-        vec![find_latest(&session_dir).expect("No session files found")]
+        let latest = find_latest(&session_dir).map_err(|_| {
+            Error::Session(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no sessions to plot in {}", session_dir.display()),
+            ))
+        })?;
+        vec![latest]
     } else {
         session_ids
             .iter()
             .map(|id| session_dir.join(format!("session_{}.csv", id)))
             .collect()
     };
+    if let Some(missing) = paths.iter().find(|path| !path.exists()) {
+        return Err(Error::Session(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{}: no such session", missing.display()),
+        )));
+    }
 
     let script = find_plot_script();
     let mut cmd = Command::new("python3");
@@ -30,6 +50,11 @@ pub fn plot_maker(session_ids: &[u16], scale: ScalingPlot) {
         .arg("--temp-steps")
         .arg(scale.number_of_steps_for_graph.to_string());
 
+    if !selection.is_empty() {
+        cmd.arg("--only")
+            .arg(selected_ids(&paths, selection)?.join(","));
+    }
+
     for p in &paths {
         cmd.arg(p);
     }
@@ -39,19 +64,78 @@ pub fn plot_maker(session_ids: &[u16], scale: ScalingPlot) {
             let _ = child.wait();
         }
         Err(e) => {
-            eprintln!("Failed to launch plot: {}. Is python3+matplotlib installed?", e);
+            eprintln!(
+                "Failed to launch plot: {}. Is python3+matplotlib installed?",
+                e
+            );
         }
     }
+    Ok(())
+}
+
+/// IDs of the recorded channels the selection keeps, matched against the
+/// `# Sensor:<type>,<id>,<label>` header lines of the sessions.
+fn selected_ids(paths: &[PathBuf], selection: &Selection) -> error::Result<Vec<String>> {
+    let mut channels: Vec<(String, String, String)> = Vec::new();
+    for path in paths {
+        let text = fs::read_to_string(path).map_err(|e| {
+            Error::Session(io::Error::new(
+                e.kind(),
+                format!("{}: {}", path.display(), e),
+            ))
+        })?;
+        for line in text.lines() {
+            let Some(fields) = line.strip_prefix("# Sensor:") else {
+                continue;
+            };
+            let mut parts = fields.splitn(3, ',');
+            if let (Some(tag), Some(id), Some(label)) = (parts.next(), parts.next(), parts.next()) {
+                channels.push((tag.to_string(), id.to_string(), label.to_string()));
+            }
+        }
+    }
+
+    let candidates: Vec<Candidate> = channels
+        .iter()
+        .map(|(tag, id, label)| {
+            let (kind, device) = kinds_from_tag(tag);
+            Candidate {
+                kind,
+                device,
+                chip: id.split('/').next().unwrap_or_default(),
+                label,
+                id,
+            }
+        })
+        .collect();
+    if let Some(selector) = selection.unmatched(&candidates) {
+        return Err(Error::Selector(format!(
+            "'{}' matches no recorded sensor",
+            selector
+        )));
+    }
+
+    let mut ids: Vec<String> = candidates
+        .iter()
+        .filter(|candidate| selection.selects(candidate))
+        .map(|candidate| candidate.id.to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
 }
 
 fn find_latest(dir: &PathBuf) -> io::Result<PathBuf> {
     let mut paths: Vec<_> = fs::read_dir(dir)?
         .filter_map(|r| r.ok())
         .map(|e| e.path())
-        .filter(|p| p.extension().map_or(false, |ext| ext == "csv"))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
         .collect();
     paths.sort();
-    paths.last().cloned().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no sessions"))
+    paths
+        .last()
+        .cloned()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no sessions"))
 }
 
 fn find_plot_script() -> PathBuf {
@@ -0,0 +1,252 @@
+use crate::sensors::{ChannelKind, DeviceKind, SensorLabel};
+use std::{fmt, str::FromStr};
+
+/// One `--sensor`/`--exclude` value: `kind:<kind>`, `chip:<glob>`,
+/// `label:<glob>` or `id:<glob>`. A bare value is a kind when it names one,
+/// otherwise an ID glob.
+#[derive(Clone)]
+pub enum Selector {
+    Channel(ChannelKind),
+    Device(DeviceKind),
+    Chip(String),
+    Label(String),
+    Id(String),
+}
+
+/// What one channel of a sensor or a recorded session is matched on.
+pub struct Candidate<'a> {
+    pub kind: Option<ChannelKind>,
+    pub device: Option<DeviceKind>,
+    pub chip: &'a str,
+    pub label: &'a str,
+    pub id: &'a str,
+}
+
+impl<'a> From<&'a SensorLabel> for Candidate<'a> {
+    fn from(sensor: &'a SensorLabel) -> Self {
+        Candidate {
+            kind: Some(sensor.kind),
+            device: Some(sensor.device),
+            chip: &sensor.chip,
+            label: &sensor.label,
+            id: &sensor.id,
+        }
+    }
+}
+
+impl Selector {
+    pub fn matches(&self, candidate: &Candidate) -> bool {
+        match self {
+            Selector::Channel(kind) => candidate.kind == Some(*kind),
+            Selector::Device(device) => candidate.device == Some(*device),
+            Selector::Chip(pattern) => glob_match(pattern, candidate.chip),
+            Selector::Label(pattern) => glob_match(pattern, candidate.label),
+            Selector::Id(pattern) => glob_match(pattern, candidate.id),
+        }
+    }
+}
+
+fn parse_kind(name: &str) -> Option<Selector> {
+    ChannelKind::from_name(name)
+        .map(Selector::Channel)
+        .or_else(|| DeviceKind::from_name(name).map(Selector::Device))
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let Some((field, pattern)) = value.split_once(':') else {
+            if value.is_empty() {
+                return Err("empty selector".to_string());
+            }
+            return Ok(parse_kind(value).unwrap_or_else(|| Selector::Id(value.to_string())));
+        };
+        // PCI addresses in IDs contain ':', so only known fields split.
+        let pattern = pattern.to_string();
+        match field {
+            "kind" => parse_kind(&pattern).ok_or_else(|| {
+                format!(
+                    "unknown kind '{}' (temp, fan, pwm, power, volt, curr, cooling, freq, \
+                     util, load, state, capacity, throttle, pressure, or a device: cpu, gpu, \
+                     storage, board, ...)",
+                    pattern
+                )
+            }),
+            "chip" => Ok(Selector::Chip(pattern)),
+            "label" => Ok(Selector::Label(pattern)),
+            "id" => Ok(Selector::Id(pattern)),
+            _ => Ok(Selector::Id(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Selector::Channel(kind) => write!(f, "kind:{}", kind.name()),
+            Selector::Device(device) => write!(f, "kind:{}", device.tag().to_lowercase()),
+            Selector::Chip(pattern) => write!(f, "chip:{}", pattern),
+            Selector::Label(pattern) => write!(f, "label:{}", pattern),
+            Selector::Id(pattern) => write!(f, "id:{}", pattern),
+        }
+    }
+}
+
+/// Channels to keep: any `include` selector matches (or there are none) and
+/// no `exclude` selector does.
+#[derive(Clone, Default)]
+pub struct Selection {
+    pub include: Vec<Selector>,
+    pub exclude: Vec<Selector>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn selects(&self, candidate: &Candidate) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|s| s.matches(candidate)))
+            && !self.exclude.iter().any(|s| s.matches(candidate))
+    }
+
+    /// The first selector that matches none of `candidates`.
+    pub fn unmatched(&self, candidates: &[Candidate]) -> Option<&Selector> {
+        self.include
+            .iter()
+            .chain(&self.exclude)
+            .find(|selector| !candidates.iter().any(|c| selector.matches(c)))
+    }
+}
+
+/// Case-insensitive glob with `*` (any run) and `?` (one character).
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    p = star + 1;
+                    t = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, Candidate, Selection, Selector};
+    use crate::sensors::{ChannelKind, DeviceKind};
+
+    fn parse(value: &str) -> String {
+        value.parse::<Selector>().unwrap().to_string()
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("k10temp", "k10temp"));
+        assert!(glob_match("K10*", "k10temp"));
+        assert!(glob_match("nct67??", "nct6798"));
+        assert!(!glob_match("nct67??", "nct679"));
+        // Anchored at both ends.
+        assert!(!glob_match("temp", "k10temp"));
+        assert!(!glob_match("k10", "k10temp"));
+        assert!(glob_match("*temp", "k10temp"));
+        // Empty patterns match only empty text; `*` matches anything.
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "k10temp"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "k10temp"));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn globs_backtrack() {
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(glob_match("a*b*c", "axxbyybzc"));
+        assert!(glob_match("a*b*c", "abcbc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(!glob_match("a*b*c", "acb"));
+        assert!(glob_match("*/temp?", "k10temp/0000:00:18.3/temp3"));
+        assert!(glob_match("*a*a*a", "aaaa"));
+        assert!(!glob_match("*a*a*a*a*a", "aaaa"));
+    }
+
+    #[test]
+    fn parses_selectors() {
+        assert_eq!(parse("fan"), "kind:fan");
+        assert_eq!(parse("kind:cpu"), "kind:cpu");
+        assert_eq!(parse("chip:nct*"), "chip:nct*");
+        assert_eq!(parse("label:Tccd*"), "label:Tccd*");
+        assert_eq!(parse(" id:k10temp/* "), "id:k10temp/*");
+        // Bare values that name no kind are ID globs.
+        assert_eq!(parse("k10temp/*"), "id:k10temp/*");
+        // Unknown prefixes are part of the ID: PCI addresses contain ':'.
+        assert_eq!(parse("0000:03:00.0"), "id:0000:03:00.0");
+        assert_eq!(parse("amdgpu/0000:0b:00.0/*"), "id:amdgpu/0000:0b:00.0/*");
+        assert_eq!(parse("foo:bar"), "id:foo:bar");
+
+        assert!(" ".parse::<Selector>().is_err());
+        let error = "kind:warp".parse::<Selector>().err().unwrap();
+        assert!(error.starts_with("unknown kind 'warp'"), "{}", error);
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let candidate = |chip, id| Candidate {
+            kind: Some(ChannelKind::Temperature),
+            device: Some(DeviceKind::Cpu),
+            chip,
+            label: "Tctl",
+            id,
+        };
+        let k10temp = candidate("k10temp", "k10temp/0000:00:18.3/temp1");
+        let nvme = candidate("nvme", "nvme/0000:03:00.0/temp1");
+
+        assert!(Selection::default().selects(&k10temp));
+
+        let selectors = |values: &[&str]| -> Vec<Selector> {
+            values.iter().map(|value| value.parse().unwrap()).collect()
+        };
+        let selection = Selection {
+            include: selectors(&["temp"]),
+            exclude: selectors(&["chip:nvme"]),
+        };
+        assert!(selection.selects(&k10temp));
+        assert!(!selection.selects(&nvme));
+
+        let selection = Selection {
+            include: selectors(&["id:k10temp/*"]),
+            exclude: selectors(&["kind:cpu"]),
+        };
+        assert!(!selection.selects(&k10temp));
+
+        let selection = Selection {
+            include: selectors(&["chip:nvme", "fan"]),
+            exclude: Vec::new(),
+        };
+        assert!(!selection.selects(&k10temp));
+        assert!(selection.selects(&nvme));
+        let unmatched = selection.unmatched(&[k10temp, nvme]).unwrap();
+        assert_eq!(unmatched.to_string(), "kind:fan");
+    }
+}
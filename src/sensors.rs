@@ -1,73 +1,663 @@
-use std::fs;
+mod calibrate;
+mod classify;
+mod cpufreq;
+mod derived;
+mod gpu;
+mod hwmon;
+mod lmsensors;
+mod nvidia;
+mod power_supply;
+mod procfs;
+mod rapl;
+mod storage;
+mod thermal;
+mod throttle;
+mod topology;
 
+use crate::{
+    error::{self, Error},
+    selector::{Candidate, Selection},
+    Config,
+};
+use std::{fs, io, path::Path};
+
+pub use calibrate::{Calibrated, Calibration};
+pub use classify::Classifier;
+pub use cpufreq::CpuFreqSource;
+pub use derived::VirtualSensor;
+pub use gpu::GpuSource;
+pub use hwmon::HwmonSource;
+pub use lmsensors::LmSensorsConfig;
+pub use nvidia::NvidiaSmiSource;
+pub use power_supply::PowerSupplySource;
+pub use procfs::ProcStatSource;
+pub use rapl::RaplSource;
+pub use thermal::{CoolingDeviceSource, ThermalZoneSource};
+pub use throttle::ThrottleSource;
+pub use topology::{CpuTopology, Placement};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeviceKind {
+    Cpu,
+    Gpu,
+    Storage,
+    Board,
+    Memory,
+    Wireless,
+    Chipset,
+    Battery,
+    #[default]
+    Unknown,
+}
+
+impl DeviceKind {
+    /// Parses the names used in the config file and on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cpu" => Some(DeviceKind::Cpu),
+            "gpu" => Some(DeviceKind::Gpu),
+            "storage" | "nvme" | "disk" => Some(DeviceKind::Storage),
+            "board" | "motherboard" => Some(DeviceKind::Board),
+            "memory" | "dimm" => Some(DeviceKind::Memory),
+            "wireless" | "wifi" => Some(DeviceKind::Wireless),
+            "chipset" | "pch" => Some(DeviceKind::Chipset),
+            "battery" => Some(DeviceKind::Battery),
+            "unknown" | "other" => Some(DeviceKind::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            DeviceKind::Cpu => "CPU",
+            DeviceKind::Gpu => "GPU",
+            DeviceKind::Storage => "STORAGE",
+            DeviceKind::Board => "BOARD",
+            DeviceKind::Memory => "MEMORY",
+            DeviceKind::Wireless => "WIRELESS",
+            DeviceKind::Chipset => "CHIPSET",
+            DeviceKind::Battery => "BATTERY",
+            DeviceKind::Unknown => "OTHER",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelKind {
+    #[default]
+    Temperature,
+    Fan,
+    Power,
+    Voltage,
+    Current,
+    Cooling,
+    Frequency,
+    Utilization,
+    Load,
+    /// Fan PWM duty cycle, in percent.
+    Pwm,
+    /// On/off state: 1 when an AC adapter is online.
+    State,
+    /// Battery charge, in percent.
+    Capacity,
+    /// Thermal throttling events since the previous frame.
+    Throttle,
+    /// Share of the frame tasks spent stalled, in percent (PSI).
+    Pressure,
+}
+
+impl ChannelKind {
+    /// Parses the kind names used by `--sensor` selectors.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "temp" | "temperature" => Some(ChannelKind::Temperature),
+            "fan" => Some(ChannelKind::Fan),
+            "power" => Some(ChannelKind::Power),
+            "volt" | "voltage" | "in" => Some(ChannelKind::Voltage),
+            "curr" | "current" => Some(ChannelKind::Current),
+            "cooling" => Some(ChannelKind::Cooling),
+            "freq" | "frequency" => Some(ChannelKind::Frequency),
+            "util" | "utilization" => Some(ChannelKind::Utilization),
+            "load" => Some(ChannelKind::Load),
+            "pwm" => Some(ChannelKind::Pwm),
+            "state" | "ac" => Some(ChannelKind::State),
+            "capacity" | "charge" => Some(ChannelKind::Capacity),
+            "throttle" => Some(ChannelKind::Throttle),
+            "pressure" | "psi" => Some(ChannelKind::Pressure),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChannelKind::Temperature => "temp",
+            ChannelKind::Fan => "fan",
+            ChannelKind::Power => "power",
+            ChannelKind::Voltage => "volt",
+            ChannelKind::Current => "curr",
+            ChannelKind::Cooling => "cooling",
+            ChannelKind::Frequency => "freq",
+            ChannelKind::Utilization => "util",
+            ChannelKind::Load => "load",
+            ChannelKind::Pwm => "pwm",
+            ChannelKind::State => "state",
+            ChannelKind::Capacity => "capacity",
+            ChannelKind::Throttle => "throttle",
+            ChannelKind::Pressure => "pressure",
+        }
+    }
+
+    fn from_channel(channel: &str) -> Option<Self> {
+        match channel.trim_end_matches(|c: char| c.is_ascii_digit()) {
+            "temp" => Some(ChannelKind::Temperature),
+            "fan" => Some(ChannelKind::Fan),
+            "power" => Some(ChannelKind::Power),
+            "in" => Some(ChannelKind::Voltage),
+            "curr" => Some(ChannelKind::Current),
+            "pwm" => Some(ChannelKind::Pwm),
+            _ => None,
+        }
+    }
+
+    /// Divisor turning the raw sysfs integer into the display unit.
+    fn sysfs_divisor(self) -> f64 {
+        match self {
+            ChannelKind::Temperature
+            | ChannelKind::Voltage
+            | ChannelKind::Current
+            | ChannelKind::Frequency => 1000.0,
+            ChannelKind::Fan
+            | ChannelKind::Cooling
+            | ChannelKind::Utilization
+            | ChannelKind::Load
+            | ChannelKind::State
+            | ChannelKind::Capacity
+            | ChannelKind::Throttle
+            | ChannelKind::Pressure => 1.0,
+            ChannelKind::Power => 1_000_000.0,
+            // 0-255 duty cycle to percent.
+            ChannelKind::Pwm => 2.55,
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            ChannelKind::Temperature => "°C",
+            ChannelKind::Fan => " RPM",
+            ChannelKind::Power => " W",
+            ChannelKind::Voltage => " V",
+            ChannelKind::Current => " A",
+            ChannelKind::Cooling => "",
+            ChannelKind::Frequency => " MHz",
+            ChannelKind::Utilization
+            | ChannelKind::Pwm
+            | ChannelKind::Capacity
+            | ChannelKind::Pressure => "%",
+            ChannelKind::Load | ChannelKind::State | ChannelKind::Throttle => "",
+        }
+    }
+
+    /// Decimal places used when writing values of this kind.
+    pub fn decimals(self, precision: usize) -> usize {
+        match self {
+            ChannelKind::Temperature
+            | ChannelKind::Power
+            | ChannelKind::Utilization
+            | ChannelKind::Pressure => precision,
+            ChannelKind::Fan
+            | ChannelKind::Cooling
+            | ChannelKind::Frequency
+            | ChannelKind::Pwm
+            | ChannelKind::State
+            | ChannelKind::Capacity
+            | ChannelKind::Throttle => 0,
+            ChannelKind::Voltage | ChannelKind::Current => 3,
+            ChannelKind::Load => 2,
+        }
+    }
+}
+
+/// One channel reading. Sources fill in what they know and leave the rest
+/// to `..Default::default()`.
+#[derive(Clone, Default)]
 pub struct SensorLabel {
+    /// Stable `chip/device/channel` identity; `label` is display-only.
+    pub id: String,
     pub label: String,
-    pub is_cpu: bool,
-    pub is_amd_gpu: bool,
-    pub is_nvme: bool,
-    pub temp: u32,
-}
-
-pub fn search_sensors() -> std::io::Result<Vec<SensorLabel>> {
-    let mut collected_data: Vec<SensorLabel> = Vec::new();
-
-    let hwmon_paths = fs::read_dir("/sys/class/hwmon/")
-        .expect("Could not read the sys/class/hwmon directory")
-        .filter_map(Result::ok)
-        .map(|entry| entry.path())
-        .filter(|path| {
-            path.is_dir()
-                && path
-                    .file_name()
-                    .map_or(false, |name| name.to_string_lossy().starts_with("hwmon"))
-        });
-
-    for path in hwmon_paths {
-        let device_name = fs::read_to_string(path.join("name"))
-            .expect("Unable to read_to_string in hwmon path")
-            .trim()
-            .to_string();
-        let is_nvme = device_name.contains("nvme");
-        let is_cpu = device_name.contains("coretemp") || device_name.contains("k10temp");
-        let is_amd_gpu = device_name.contains("amdgpu");
-
-        if let Ok(entries) = fs::read_dir(&path) {
-            for entry in entries.filter_map(Result::ok) {
-                let file_name = entry.file_name().to_string_lossy().to_string();
-                if file_name.starts_with("temp") && file_name.ends_with("_input") {
-                    let temp_string =
-                        fs::read_to_string(entry.path()).expect("Unable to entry.path");
-                    let temp_value: u32 = temp_string.trim().parse().unwrap_or(0) / 1000;
-                    let label_path = entry
-                        .path()
-                        .with_file_name(file_name.replace("_input", "_label"));
-                    let label_string = fs::read_to_string(label_path)
-                        .unwrap_or("Unknown".to_string())
-                        .trim()
-                        .to_string();
-                    collected_data.push(SensorLabel {
-                        label: label_string,
-                        is_cpu,
-                        is_nvme,
-                        is_amd_gpu,
-                        temp: temp_value,
-                    });
+    /// lm-sensors `label` for the channel. It replaces `label` only after
+    /// topology placement, which matches on the driver's labels.
+    pub lm_label: Option<String>,
+    pub chip: String,
+    pub kind: ChannelKind,
+    pub device: DeviceKind,
+    /// `None` when the channel failed to read this frame.
+    pub value: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+    pub crit_hyst: Option<f64>,
+    pub alarm: Option<bool>,
+    /// Package, CCD or core behind a per-core CPU reading.
+    pub placement: Option<Placement>,
+    /// Set when a `[sensor]` config section corrected this channel.
+    pub calibration: Option<Calibrated>,
+    /// Which physical drive (or card) the chip belongs to, when the driver
+    /// label alone does not say.
+    pub identity: Option<DeviceIdentity>,
+}
+
+/// Model, serial and firmware of the device behind a chip, as sysfs
+/// reports them.
+#[derive(Clone, Default)]
+pub struct DeviceIdentity {
+    /// Kernel name the user knows the device by: `nvme0n1`, `sda`.
+    pub name: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+}
+
+impl DeviceIdentity {
+    /// Short form for the live view: `nvme0n1 Samsung SSD 980 PRO 1TB`.
+    pub fn describe(&self) -> String {
+        [&self.name, &self.model]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `key=value` fields for the `# Device:` session header line.
+    pub fn header_fields(&self) -> String {
+        [
+            ("name", &self.name),
+            ("model", &self.model),
+            ("serial", &self.serial),
+            ("firmware", &self.firmware),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            // Keep the header comma separated.
+            value
+                .as_ref()
+                .map(|value| format!("{}={}", key, value.replace(',', " ")))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+impl SensorLabel {
+    /// Value with its unit for display, or `n/a` for a missing sample.
+    pub fn display_value(&self, precision: usize) -> String {
+        match self.value {
+            Some(value) if self.kind == ChannelKind::State => {
+                if value != 0.0 { "on" } else { "off" }.to_string()
+            }
+            Some(value) => format!(
+                "{:.*}{}",
+                self.kind.decimals(precision),
+                value,
+                self.kind.unit()
+            ),
+            None => "n/a".to_string(),
+        }
+    }
+}
+
+pub struct TripPoint {
+    pub zone: String,
+    pub kind: String,
+    pub temp: f64,
+}
+
+pub trait SensorSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>>;
+
+    /// Reference temperatures for the session header, such as thermal trips.
+    fn trip_points(&self) -> Vec<TripPoint> {
+        Vec::new()
+    }
+}
+
+/// Concatenates the readings of several backends, in order. Fallback
+/// backends only contribute channels the primary ones did not already cover.
+/// Temperature channels of unclassified devices are dropped unless
+/// `keep_unclassified` is set. Per-core CPU channels are placed on the
+/// CPU topology, `[sensor]` calibrations are applied, `[virtual]` channels
+/// are computed from the result, and only channels the selection keeps are
+/// returned.
+pub struct MultiSource {
+    sources: Vec<Box<dyn SensorSource>>,
+    fallbacks: Vec<Box<dyn SensorSource>>,
+    keep_unclassified: bool,
+    topology: CpuTopology,
+    calibrations: Vec<Calibration>,
+    virtuals: Vec<VirtualSensor>,
+    selection: Selection,
+    /// Whether every selector and virtual input has been checked against a
+    /// first read.
+    selection_checked: bool,
+}
+
+impl MultiSource {
+    pub fn new(sources: Vec<Box<dyn SensorSource>>) -> Self {
+        MultiSource {
+            sources,
+            fallbacks: Vec::new(),
+            keep_unclassified: false,
+            topology: CpuTopology::default(),
+            calibrations: Vec::new(),
+            virtuals: Vec::new(),
+            selection: Selection::default(),
+            selection_checked: false,
+        }
+    }
+
+    pub fn with_calibrations(mut self, calibrations: Vec<Calibration>) -> Self {
+        self.calibrations = calibrations;
+        self
+    }
+
+    pub fn with_virtuals(mut self, virtuals: Vec<VirtualSensor>) -> Self {
+        self.virtuals = virtuals;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn with_topology(mut self, topology: CpuTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn keep_unclassified(mut self, keep: bool) -> Self {
+        self.keep_unclassified = keep;
+        self
+    }
+
+    pub fn with_fallback(mut self, source: Box<dyn SensorSource>) -> Self {
+        self.fallbacks.push(source);
+        self
+    }
+
+    /// Every backend available under the configured sysfs and procfs trees.
+    pub fn system(config: &Config) -> Self {
+        let sysfs_root = &config.sysfs_root;
+        let classifier = &config.classifier;
+        MultiSource::new(vec![
+            Box::new(HwmonSource::new(
+                sysfs_root,
+                classifier.clone(),
+                config.lm_sensors.clone(),
+            )),
+            Box::new(RaplSource::new(sysfs_root)),
+            Box::new(CoolingDeviceSource::new(sysfs_root, classifier.clone())),
+            Box::new(CpuFreqSource::new(sysfs_root)),
+            Box::new(GpuSource::new(sysfs_root)),
+            Box::new(NvidiaSmiSource::new(config.nvidia_smi.clone())),
+            Box::new(ProcStatSource::new(&config.procfs_root)),
+            Box::new(ThrottleSource::new(sysfs_root)),
+        ])
+        .with_fallback(Box::new(ThermalZoneSource::new(
+            sysfs_root,
+            classifier.clone(),
+        )))
+        // Batteries with their own hwmon chip report through it.
+        .with_fallback(Box::new(PowerSupplySource::new(sysfs_root)))
+        .keep_unclassified(config.keep_unclassified)
+        .with_topology(CpuTopology::new(sysfs_root))
+        .with_calibrations(config.calibrations.clone())
+        .with_virtuals(config.virtuals.clone())
+        .with_selection(config.selection.clone())
+    }
+}
+
+impl SensorSource for MultiSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        for source in &mut self.sources {
+            collected_data.extend(source.read_sensors()?);
+        }
+
+        for source in &mut self.fallbacks {
+            let fresh: Vec<SensorLabel> = source
+                .read_sensors()?
+                .into_iter()
+                .filter(|sensor| !collected_data.iter().any(|known| covers(known, sensor)))
+                .collect();
+            collected_data.extend(fresh);
+        }
+
+        if !self.keep_unclassified {
+            collected_data.retain(|sensor| {
+                sensor.kind != ChannelKind::Temperature || sensor.device != DeviceKind::Unknown
+            });
+        }
+        // Placement reads driver labels, so it runs before lm-sensors
+        // labels and aliases apply.
+        self.topology.place(&mut collected_data);
+        for sensor in &mut collected_data {
+            if let Some(label) = sensor.lm_label.take() {
+                sensor.label = label;
+            }
+        }
+        calibrate::calibrate(&mut collected_data, &self.calibrations);
+        derived::derive(&mut collected_data, &self.virtuals);
+
+        if !self.selection_checked {
+            self.selection_checked = true;
+            for virtual_sensor in &self.virtuals {
+                if let Some(name) = virtual_sensor.unmatched(&collected_data) {
+                    return Err(Error::Config(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "[virtual {}]: '{}' matches no sensor",
+                            virtual_sensor.name, name
+                        ),
+                    )));
                 }
             }
+            let candidates: Vec<Candidate> = collected_data.iter().map(Candidate::from).collect();
+            if let Some(selector) = self.selection.unmatched(&candidates) {
+                return Err(Error::Selector(format!("'{}' matches no sensor", selector)));
+            }
         }
+        let selection = &self.selection;
+        collected_data.retain(|sensor| selection.selects(&Candidate::from(sensor)));
+        Ok(collected_data)
+    }
+
+    fn trip_points(&self) -> Vec<TripPoint> {
+        self.sources
+            .iter()
+            .chain(&self.fallbacks)
+            .flat_map(|source| source.trip_points())
+            .collect()
     }
+}
+
+/// Whether `known` already reports the device behind `other`: the same chip,
+/// or the same CPU or GPU seen through another driver (x86_pkg_temp next to
+/// coretemp).
+fn covers(known: &SensorLabel, other: &SensorLabel) -> bool {
+    known.kind == other.kind
+        && (known.chip == other.chip
+            || (matches!(known.device, DeviceKind::Cpu | DeviceKind::Gpu)
+                && known.device == other.device))
+}
+
+/// Joins id parts with `/`, keeping each part free of characters that would
+/// break the CSV columns or JSON keys.
+pub fn sensor_id(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| {
+            part.trim()
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "._:-".contains(c) {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A trimmed sysfs text attribute; `None` when missing or empty.
+fn read_attr(path: &Path) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
 
-    Ok(collected_data)
+/// Thresholds the driver reported for this channel, in header order.
+pub fn thresholds(sensor: &SensorLabel) -> Vec<(&'static str, f64)> {
+    [
+        ("min", sensor.min),
+        ("max", sensor.max),
+        ("crit", sensor.crit),
+        ("crit_hyst", sensor.crit_hyst),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|v| (name, v)))
+    .collect()
+}
+
+/// Inverse of [`device_type`], for channels read back from a session file.
+pub fn kinds_from_tag(tag: &str) -> (Option<ChannelKind>, Option<DeviceKind>) {
+    match tag {
+        "FAN" => (Some(ChannelKind::Fan), None),
+        "POWER" => (Some(ChannelKind::Power), None),
+        "VOLT" => (Some(ChannelKind::Voltage), None),
+        "CURR" => (Some(ChannelKind::Current), None),
+        "COOLING" => (Some(ChannelKind::Cooling), None),
+        "FREQ" => (Some(ChannelKind::Frequency), None),
+        "UTIL" => (Some(ChannelKind::Utilization), None),
+        "LOAD" => (Some(ChannelKind::Load), None),
+        "PWM" => (Some(ChannelKind::Pwm), None),
+        "STATE" => (Some(ChannelKind::State), None),
+        "CAPACITY" => (Some(ChannelKind::Capacity), None),
+        "THROTTLE" => (Some(ChannelKind::Throttle), None),
+        "PSI" => (Some(ChannelKind::Pressure), None),
+        tag => (
+            Some(ChannelKind::Temperature),
+            DeviceKind::from_name(tag).or(Some(DeviceKind::Unknown)),
+        ),
+    }
 }
 
 pub fn device_type(sensor: &SensorLabel) -> &'static str {
-    if sensor.is_cpu {
-        "CPU"
-    } else if sensor.is_nvme {
-        "NVME"
-    } else if sensor.is_amd_gpu {
-        "GPU"
-    } else {
-        "Unknown"
+    match sensor.kind {
+        ChannelKind::Fan => "FAN",
+        ChannelKind::Power => "POWER",
+        ChannelKind::Voltage => "VOLT",
+        ChannelKind::Current => "CURR",
+        ChannelKind::Cooling => "COOLING",
+        ChannelKind::Frequency => "FREQ",
+        ChannelKind::Utilization => "UTIL",
+        ChannelKind::Load => "LOAD",
+        ChannelKind::Pwm => "PWM",
+        ChannelKind::State => "STATE",
+        ChannelKind::Capacity => "CAPACITY",
+        ChannelKind::Throttle => "THROTTLE",
+        ChannelKind::Pressure => "PSI",
+        ChannelKind::Temperature => sensor.device.tag(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelKind, Classifier, LmSensorsConfig, MultiSource, SensorSource};
+    use crate::{fixture::Fixture, selector::Selection, Config};
+
+    fn config(root: &Fixture) -> Config {
+        Config {
+            delay: 1000,
+            no_graph: true,
+            max_plot_temp: 100,
+            temp_steps: 10,
+            sysfs_root: root.path().join("sys"),
+            procfs_root: root.path().join("proc"),
+            classifier: Classifier::new(Vec::new()),
+            keep_unclassified: false,
+            calibrations: Vec::new(),
+            virtuals: Vec::new(),
+            trigger: None,
+            lm_sensors: LmSensorsConfig::default(),
+            nvidia_smi: Some(root.path().join("bin/nvidia-smi")),
+            selection: Selection::default(),
+            precision: 1,
+        }
+    }
+
+    /// Two logical CPUs on one core, the k10temp chip that measures them,
+    /// and a thermal zone reporting the same package.
+    fn ryzen(root: &Fixture) {
+        for cpu in 0..2 {
+            let topology = format!("sys/devices/system/cpu/cpu{}/topology", cpu);
+            root.write(&format!("{}/physical_package_id", topology), "0\n")
+                .write(&format!("{}/core_id", topology), "0\n");
+        }
+        root.write("sys/class/hwmon/hwmon0/name", "k10temp\n")
+            .write("sys/class/hwmon/hwmon0/temp1_input", "55375\n")
+            .write("sys/class/hwmon/hwmon0/temp1_label", "Tctl\n")
+            .link(
+                "sys/class/hwmon/hwmon0/device",
+                "sys/devices/pci0000:00/0000:00:18.3",
+            )
+            .write("sys/class/thermal/thermal_zone0/type", "x86_pkg_temp\n")
+            .write("sys/class/thermal/thermal_zone0/temp", "54000\n");
+    }
+
+    #[test]
+    fn places_before_applying_lm_sensors_labels() {
+        let root = Fixture::new();
+        ryzen(&root);
+        root.write(
+            "etc/sensors3.conf",
+            "chip \"k10temp-*\"\n  label temp1 \"CPU\"\n",
+        );
+        let mut config = config(&root);
+        config.lm_sensors =
+            LmSensorsConfig::load(&[root.path().join("etc/sensors3.conf")], true).unwrap();
+
+        let sensors = MultiSource::system(&config).read_sensors().unwrap();
+        // The zone reports the package k10temp already covers.
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].id, "k10temp/0000:00:18.3/temp1");
+        assert_eq!(sensors[0].label, "CPU");
+        let placement = sensors[0].placement.as_ref().unwrap();
+        assert_eq!(placement.cpus, [0, 1]);
+    }
+
+    #[test]
+    fn falls_back_to_thermal_zones_and_power_supplies() {
+        let root = Fixture::new();
+        ryzen(&root);
+        root.remove("sys/class/hwmon/hwmon0");
+        root.write("sys/class/power_supply/AC/type", "Mains\n")
+            .write("sys/class/power_supply/AC/online", "1\n");
+
+        let sensors = MultiSource::system(&config(&root)).read_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["x86_pkg_temp/thermal_zone0/temp", "power_supply/ac/online"]
+        );
+        assert_eq!(sensors[0].value, Some(54.0));
+        assert!(sensors[1].kind == ChannelKind::State);
     }
 }
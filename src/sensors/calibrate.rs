@@ -0,0 +1,143 @@
+use super::SensorLabel;
+use crate::selector::glob_match;
+
+/// A `[sensor <id>]` config section: display alias, linear correction
+/// `value * scale + offset`, and whether to drop the channel.
+#[derive(Clone)]
+pub struct Calibration {
+    /// Sensor ID, or an ID glob.
+    pub pattern: String,
+    pub alias: Option<String>,
+    pub offset: f64,
+    pub scale: f64,
+    pub ignore: bool,
+}
+
+/// What a calibration changed on one channel, kept for the session header.
+#[derive(Clone)]
+pub struct Calibrated {
+    pub offset: f64,
+    pub scale: f64,
+    /// The label an alias replaced: the driver's, or the lm-sensors one.
+    pub driver_label: Option<String>,
+}
+
+/// Applies the first matching section to each channel, dropping ignored ones.
+/// Thresholds are corrected along with the value so alarms stay consistent.
+pub fn calibrate(sensors: &mut Vec<SensorLabel>, calibrations: &[Calibration]) {
+    if calibrations.is_empty() {
+        return;
+    }
+
+    sensors.retain_mut(|sensor| {
+        let Some(calibration) = calibrations
+            .iter()
+            .find(|c| glob_match(&c.pattern, &sensor.id))
+        else {
+            return true;
+        };
+        if calibration.ignore {
+            return false;
+        }
+
+        let correct = |value: f64| value * calibration.scale + calibration.offset;
+        sensor.value = sensor.value.map(correct);
+        sensor.min = sensor.min.map(correct);
+        sensor.max = sensor.max.map(correct);
+        sensor.crit = sensor.crit.map(correct);
+        sensor.crit_hyst = sensor.crit_hyst.map(correct);
+
+        let driver_label = calibration
+            .alias
+            .as_ref()
+            .map(|alias| std::mem::replace(&mut sensor.label, alias.clone()));
+        sensor.calibration = Some(Calibrated {
+            offset: calibration.offset,
+            scale: calibration.scale,
+            driver_label,
+        });
+        true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calibrate, Calibration};
+    use crate::sensors::SensorLabel;
+
+    fn reading(id: &str, label: &str) -> SensorLabel {
+        SensorLabel {
+            id: id.to_string(),
+            label: label.to_string(),
+            value: Some(50.0),
+            max: Some(80.0),
+            crit: Some(90.0),
+            ..Default::default()
+        }
+    }
+
+    fn section(pattern: &str) -> Calibration {
+        Calibration {
+            pattern: pattern.to_string(),
+            alias: None,
+            offset: 0.0,
+            scale: 1.0,
+            ignore: false,
+        }
+    }
+
+    #[test]
+    fn corrects_values_and_thresholds() {
+        let mut sensors = vec![
+            reading("nct6798/nct6775.656/temp7", "AUXTIN3"),
+            reading("k10temp/0000:00:18.3/temp1", "Tctl"),
+        ];
+        let calibrations = [Calibration {
+            alias: Some("CPU socket".to_string()),
+            offset: -5.0,
+            scale: 0.5,
+            ..section("nct6798/*/temp7")
+        }];
+        calibrate(&mut sensors, &calibrations);
+
+        let socket = &sensors[0];
+        assert_eq!(socket.label, "CPU socket");
+        assert_eq!(socket.value, Some(20.0));
+        assert_eq!((socket.max, socket.crit), (Some(35.0), Some(40.0)));
+        assert_eq!(socket.min, None);
+        let calibrated = socket.calibration.as_ref().unwrap();
+        assert_eq!((calibrated.offset, calibrated.scale), (-5.0, 0.5));
+        assert_eq!(calibrated.driver_label.as_deref(), Some("AUXTIN3"));
+
+        // Channels no section matches are left alone.
+        assert_eq!(sensors[1].value, Some(50.0));
+        assert!(sensors[1].calibration.is_none());
+    }
+
+    #[test]
+    fn first_matching_section_wins() {
+        let mut sensors = vec![
+            reading("acpitz/thermal_zone0/temp", "acpitz"),
+            reading("acpitz/thermal_zone1/temp", "acpitz"),
+        ];
+        let calibrations = [
+            Calibration {
+                offset: 3.0,
+                ..section("ACPITZ/thermal_zone1/*")
+            },
+            Calibration {
+                ignore: true,
+                ..section("acpitz/*")
+            },
+        ];
+        calibrate(&mut sensors, &calibrations);
+
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].id, "acpitz/thermal_zone1/temp");
+        assert_eq!(sensors[0].value, Some(53.0));
+        // Without an alias the label stays.
+        assert_eq!(sensors[0].label, "acpitz");
+        let calibrated = sensors[0].calibration.as_ref().unwrap();
+        assert_eq!(calibrated.driver_label, None);
+    }
+}
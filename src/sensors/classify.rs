@@ -0,0 +1,143 @@
+use super::DeviceKind;
+
+/// Built-in driver and thermal-zone name prefixes, most specific first.
+const BUILTIN: &[(&str, DeviceKind)] = &[
+    // CPU package/core sensors and CPU thermal zones
+    ("coretemp", DeviceKind::Cpu),
+    ("k10temp", DeviceKind::Cpu),
+    ("k8temp", DeviceKind::Cpu),
+    ("zenpower", DeviceKind::Cpu),
+    ("fam15h_power", DeviceKind::Cpu),
+    ("via_cputemp", DeviceKind::Cpu),
+    ("x86_pkg_temp", DeviceKind::Cpu),
+    ("proc_thermal", DeviceKind::Cpu),
+    ("tcpu", DeviceKind::Cpu),
+    ("cpu", DeviceKind::Cpu),
+    ("soc", DeviceKind::Cpu),
+    // GPUs
+    ("amdgpu", DeviceKind::Gpu),
+    ("radeon", DeviceKind::Gpu),
+    ("nouveau", DeviceKind::Gpu),
+    ("nvidia", DeviceKind::Gpu),
+    ("i915", DeviceKind::Gpu),
+    ("xe", DeviceKind::Gpu),
+    ("gpu", DeviceKind::Gpu),
+    // Drives
+    ("nvme", DeviceKind::Storage),
+    ("drivetemp", DeviceKind::Storage),
+    // Super-I/O, embedded controllers and ACPI board zones
+    ("nct6", DeviceKind::Board),
+    ("nct7", DeviceKind::Board),
+    ("it8", DeviceKind::Board),
+    ("w83", DeviceKind::Board),
+    ("f71", DeviceKind::Board),
+    ("sch5", DeviceKind::Board),
+    ("asus", DeviceKind::Board),
+    ("gigabyte_wmi", DeviceKind::Board),
+    ("dell_smm", DeviceKind::Board),
+    ("thinkpad", DeviceKind::Board),
+    ("applesmc", DeviceKind::Board),
+    ("acpitz", DeviceKind::Board),
+    ("tskn", DeviceKind::Board),
+    // DIMM sensors
+    ("spd5118", DeviceKind::Memory),
+    ("jc42", DeviceKind::Memory),
+    ("ddr", DeviceKind::Memory),
+    // Wi-Fi adapters
+    ("iwlwifi", DeviceKind::Wireless),
+    ("ath1", DeviceKind::Wireless),
+    ("mt76", DeviceKind::Wireless),
+    ("mt79", DeviceKind::Wireless),
+    ("rtw", DeviceKind::Wireless),
+    ("brcmfmac", DeviceKind::Wireless),
+    ("wifi", DeviceKind::Wireless),
+    // Platform controller hubs
+    ("pch_", DeviceKind::Chipset),
+    ("i5500_temp", DeviceKind::Chipset),
+    ("sb_temp", DeviceKind::Chipset),
+    // Batteries
+    ("bat", DeviceKind::Battery),
+    ("sbs", DeviceKind::Battery),
+    ("bq2", DeviceKind::Battery),
+];
+
+/// Maps hwmon chip names and thermal zone types onto device kinds. User
+/// entries are matched before the built-in table, so they can both add
+/// drivers and override built-in ones.
+#[derive(Clone, Default)]
+pub struct Classifier {
+    overrides: Vec<(String, DeviceKind)>,
+}
+
+impl Classifier {
+    pub fn new(overrides: Vec<(String, DeviceKind)>) -> Self {
+        Classifier {
+            overrides: overrides
+                .into_iter()
+                .map(|(prefix, kind)| (prefix.to_lowercase(), kind))
+                .collect(),
+        }
+    }
+
+    pub fn classify(&self, name: &str) -> DeviceKind {
+        let name = name.to_lowercase();
+        self.overrides
+            .iter()
+            .map(|(prefix, kind)| (prefix.as_str(), *kind))
+            .chain(BUILTIN.iter().copied())
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map_or(DeviceKind::Unknown, |(_, kind)| kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Classifier, BUILTIN};
+    use crate::sensors::DeviceKind;
+
+    #[test]
+    fn builtin_table() {
+        let classifier = Classifier::default();
+        for (name, kind) in [
+            ("coretemp", DeviceKind::Cpu),
+            ("k10temp", DeviceKind::Cpu),
+            ("x86_pkg_temp", DeviceKind::Cpu),
+            ("amdgpu", DeviceKind::Gpu),
+            ("xe", DeviceKind::Gpu),
+            ("nvme", DeviceKind::Storage),
+            ("drivetemp", DeviceKind::Storage),
+            ("nct6798", DeviceKind::Board),
+            ("it8688", DeviceKind::Board),
+            ("it87", DeviceKind::Board),
+            ("acpitz", DeviceKind::Board),
+            ("spd5118", DeviceKind::Memory),
+            ("iwlwifi_1", DeviceKind::Wireless),
+            ("pch_cannonlake", DeviceKind::Chipset),
+            ("BAT0", DeviceKind::Battery),
+            ("INT3400 Thermal", DeviceKind::Unknown),
+            ("", DeviceKind::Unknown),
+        ] {
+            assert!(classifier.classify(name) == kind, "{}", name);
+        }
+    }
+
+    #[test]
+    fn no_builtin_prefix_is_shadowed() {
+        for (index, (prefix, _)) in BUILTIN.iter().enumerate() {
+            for (earlier, _) in &BUILTIN[..index] {
+                assert!(!prefix.starts_with(earlier), "{} after {}", prefix, earlier);
+            }
+        }
+    }
+
+    #[test]
+    fn overrides_come_first() {
+        let classifier = Classifier::new(vec![
+            ("NCT6798".to_string(), DeviceKind::Chipset),
+            ("acme".to_string(), DeviceKind::Gpu),
+        ]);
+        assert!(classifier.classify("nct6798") == DeviceKind::Chipset);
+        assert!(classifier.classify("nct6775") == DeviceKind::Board);
+        assert!(classifier.classify("acme_hwmon") == DeviceKind::Gpu);
+    }
+}
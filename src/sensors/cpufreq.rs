@@ -0,0 +1,66 @@
+use super::{sensor_id, ChannelKind, DeviceKind, SensorLabel, SensorSource};
+use crate::error;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Current per-core frequency from `cpufreq/scaling_cur_freq`, scaled
+/// against `cpuinfo_max_freq`.
+pub struct CpuFreqSource {
+    root: PathBuf,
+}
+
+impl CpuFreqSource {
+    pub fn new(sysfs_root: &Path) -> Self {
+        CpuFreqSource {
+            root: sysfs_root.join("devices").join("system").join("cpu"),
+        }
+    }
+}
+
+impl SensorSource for CpuFreqSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Ok(collected_data);
+        };
+
+        let mut cpus: Vec<(u32, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let index = name.strip_prefix("cpu")?.parse().ok()?;
+                Some((index, entry.path()))
+            })
+            .collect();
+        cpus.sort_by_key(|(index, _)| *index);
+
+        for (index, cpu) in cpus {
+            let cpufreq = cpu.join("cpufreq");
+            // Offline cores and VMs without a cpufreq driver have no reading.
+            let Some(khz) = read_khz(&cpufreq.join("scaling_cur_freq")) else {
+                continue;
+            };
+            let cpu_name = format!("cpu{}", index);
+            collected_data.push(SensorLabel {
+                id: sensor_id(&["cpufreq", &cpu_name, "freq"]),
+                label: cpu_name,
+                chip: "cpufreq".to_string(),
+                kind: ChannelKind::Frequency,
+                device: DeviceKind::Cpu,
+                value: Some(khz / 1000.0),
+                min: read_khz(&cpufreq.join("cpuinfo_min_freq")).map(|khz| khz / 1000.0),
+                max: read_khz(&cpufreq.join("cpuinfo_max_freq")).map(|khz| khz / 1000.0),
+                ..Default::default()
+            });
+        }
+
+        Ok(collected_data)
+    }
+}
+
+fn read_khz(path: &Path) -> Option<f64> {
+    let khz: u64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(khz as f64)
+}
@@ -0,0 +1,75 @@
+use super::{ChannelKind, DeviceKind, SensorLabel};
+use crate::{expr::Expr, selector::glob_match};
+
+/// A `[virtual <name>]` config section: a channel computed each frame from
+/// the readings of real ones, recorded as `virtual/<name>`.
+#[derive(Clone)]
+pub struct VirtualSensor {
+    pub name: String,
+    pub label: Option<String>,
+    pub kind: ChannelKind,
+    /// Device tag; taken from the inputs when they agree, else `OTHER`.
+    pub device: Option<DeviceKind>,
+    pub expr: Expr,
+}
+
+impl VirtualSensor {
+    pub fn id(&self) -> String {
+        format!("virtual/{}", self.name)
+    }
+
+    /// The first input name that matches none of `sensors`.
+    pub fn unmatched<'a>(&'a self, sensors: &[SensorLabel]) -> Option<&'a str> {
+        self.expr
+            .names()
+            .into_iter()
+            .find(|name| !sensors.iter().any(|s| glob_match(name, &s.id)))
+    }
+}
+
+/// Appends one channel per virtual sensor, in config order, so a later one
+/// can read an earlier one. A name resolves to the first channel whose ID it
+/// matches; inside `max`, `min`, `avg` and `sum` a glob takes every match.
+pub fn derive(sensors: &mut Vec<SensorLabel>, virtuals: &[VirtualSensor]) {
+    for virtual_sensor in virtuals {
+        let readings: &[SensorLabel] = sensors;
+        let matching = |name: &str| {
+            readings
+                .iter()
+                .filter(move |s| glob_match(name, &s.id))
+                .collect::<Vec<_>>()
+        };
+        let value = virtual_sensor.expr.eval_with(
+            &|name| matching(name).first().and_then(|s| s.value),
+            &|name| matching(name).iter().filter_map(|s| s.value).collect(),
+        );
+
+        let inputs: Vec<&SensorLabel> = virtual_sensor
+            .expr
+            .names()
+            .into_iter()
+            .flat_map(matching)
+            .collect();
+        let device = virtual_sensor
+            .device
+            .unwrap_or_else(|| match inputs.split_first() {
+                Some((first, rest)) if rest.iter().all(|s| s.device == first.device) => {
+                    first.device
+                }
+                _ => DeviceKind::Unknown,
+            });
+
+        sensors.push(SensorLabel {
+            id: virtual_sensor.id(),
+            label: virtual_sensor
+                .label
+                .clone()
+                .unwrap_or_else(|| virtual_sensor.name.clone()),
+            chip: "virtual".to_string(),
+            kind: virtual_sensor.kind,
+            device,
+            value,
+            ..Default::default()
+        });
+    }
+}
@@ -0,0 +1,439 @@
+use super::{
+    read_attr, read_number, sensor_id, ChannelKind, DeviceIdentity, DeviceKind, SensorLabel,
+    SensorSource,
+};
+use crate::error;
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Per-card GPU load and clocks from `/sys/class/drm/cardN`: amdgpu
+/// `gpu_busy_percent`, `mem_busy_percent` and the active `pp_dpm_sclk` and
+/// `pp_dpm_mclk` levels; i915 `gt_cur_freq_mhz`; xe `tile*/gt*/freq0`.
+/// Temperatures, fans and PWM come from the card's hwmon chip. Cards are
+/// discovered once and again when the drm listing changes; in between,
+/// each frame only re-reads the cached files.
+pub struct GpuSource {
+    root: PathBuf,
+    /// `cardN` entries seen at the last discovery, sorted.
+    listing: Option<Vec<OsString>>,
+    channels: Vec<GpuChannel>,
+}
+
+/// A discovered channel: everything but the value, and the max of a
+/// `pp_dpm_*` table, is read once, at discovery.
+struct GpuChannel {
+    template: SensorLabel,
+    reading: Reading,
+}
+
+enum Reading {
+    Number(PathBuf),
+    Dpm(PathBuf),
+}
+
+impl GpuSource {
+    pub fn new(sysfs_root: &Path) -> Self {
+        GpuSource {
+            root: sysfs_root.join("class").join("drm"),
+            listing: None,
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl SensorSource for GpuSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let listing = list_cards(&self.root);
+        if self.listing.as_ref() != Some(&listing) {
+            self.channels = discover(&self.root, &listing);
+            self.listing = Some(listing);
+        }
+
+        // A card in runtime suspend fails these reads; the channel stays
+        // with a missing sample.
+        let mut collected_data = Vec::with_capacity(self.channels.len());
+        for channel in &self.channels {
+            let mut sensor = channel.template.clone();
+            match &channel.reading {
+                Reading::Number(path) => sensor.value = read_number(path),
+                Reading::Dpm(path) => {
+                    (sensor.value, sensor.max) = read_dpm(path).unzip();
+                }
+            }
+            collected_data.push(sensor);
+        }
+        Ok(collected_data)
+    }
+}
+
+/// `cardN` entries of the drm class, sorted by index; connectors are
+/// listed as cardN-DP-1.
+fn list_cards(drm_dir: &Path) -> Vec<OsString> {
+    let Ok(entries) = fs::read_dir(drm_dir) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<(u32, OsString)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_string_lossy().strip_prefix("card")?.parse().ok()?;
+            Some((index, name))
+        })
+        .collect();
+    cards.sort();
+    cards.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Finds the load and clock files of every card in `listing`.
+fn discover(drm_dir: &Path, listing: &[OsString]) -> Vec<GpuChannel> {
+    let mut channels = Vec::new();
+    for name in listing {
+        let card = drm_dir.join(name);
+        let Ok(device) = fs::canonicalize(card.join("device")) else {
+            continue;
+        };
+        let Some(driver) = fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|link| Some(link.file_name()?.to_string_lossy().to_string()))
+        else {
+            continue;
+        };
+        let address = device
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let identity = card_identity(&device);
+        let card_name = identity
+            .as_ref()
+            .and_then(|identity| identity.name.clone())
+            .unwrap_or_else(|| address.clone());
+
+        let mut channel = |name: &str, label: &str, kind, reading, max| {
+            channels.push(GpuChannel {
+                template: SensorLabel {
+                    id: sensor_id(&[&driver, &address, name]),
+                    label: format!("{} {}", card_name, label),
+                    chip: driver.clone(),
+                    kind,
+                    device: DeviceKind::Gpu,
+                    max,
+                    identity: identity.clone(),
+                    ..Default::default()
+                },
+                reading,
+            });
+        };
+
+        for (file, name, label) in [
+            ("gpu_busy_percent", "gpu_busy", "busy"),
+            ("mem_busy_percent", "mem_busy", "VRAM busy"),
+        ] {
+            let path = device.join(file);
+            if path.exists() {
+                let reading = Reading::Number(path);
+                channel(name, label, ChannelKind::Utilization, reading, None);
+            }
+        }
+        for clock in ["sclk", "mclk"] {
+            let path = device.join(format!("pp_dpm_{}", clock));
+            if path.exists() {
+                let reading = Reading::Dpm(path);
+                channel(clock, clock, ChannelKind::Frequency, reading, None);
+            }
+        }
+
+        let gt_freq = card.join("gt_cur_freq_mhz");
+        if gt_freq.exists() {
+            let max = read_number(&card.join("gt_RP0_freq_mhz"))
+                .or_else(|| read_number(&card.join("gt_max_freq_mhz")));
+            let reading = Reading::Number(gt_freq);
+            channel("gt_freq", "GT", ChannelKind::Frequency, reading, max);
+        }
+        for gt in xe_gts(&device) {
+            let freq = gt.path.join("freq0");
+            channel(
+                &format!("{}_freq", gt.name),
+                &gt.name,
+                ChannelKind::Frequency,
+                Reading::Number(freq.join("cur_freq")),
+                read_number(&freq.join("rp0_freq")).or_else(|| read_number(&freq.join("max_freq"))),
+            );
+        }
+    }
+    channels
+}
+
+/// DRM card name, product name (or PCI vendor:device) and VBIOS of the GPU
+/// at `device`, a PCI function directory.
+pub fn card_identity(device: &Path) -> Option<DeviceIdentity> {
+    let drm = device.join("drm");
+    let mut cards: Vec<String> = fs::read_dir(&drm)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.strip_prefix("card")
+                .is_some_and(|index| index.parse::<u32>().is_ok())
+        })
+        .collect();
+    cards.sort();
+
+    let pci_id = || {
+        let id = |file: &str| {
+            let value = read_attr(&device.join(file))?;
+            Some(value.trim_start_matches("0x").to_string())
+        };
+        Some(format!("{}:{}", id("vendor")?, id("device")?))
+    };
+    Some(DeviceIdentity {
+        name: Some(cards.into_iter().next()?),
+        model: read_attr(&device.join("product_name")).or_else(pci_id),
+        serial: read_attr(&device.join("serial_number")),
+        firmware: read_attr(&device.join("vbios_version")),
+    })
+}
+
+struct XeGt {
+    /// `gt0`, or `tile1-gt0` past the first tile.
+    name: String,
+    path: PathBuf,
+}
+
+/// Graphics tiles of an xe device, each with its own frequency domain.
+fn xe_gts(device: &Path) -> Vec<XeGt> {
+    let mut gts = Vec::new();
+    for tile in sorted_entries(device, "tile") {
+        for gt in sorted_entries(&tile, "gt") {
+            if !gt.join("freq0").is_dir() {
+                continue;
+            }
+            let tile_name = tile.file_name().unwrap_or_default().to_string_lossy();
+            let gt_name = gt.file_name().unwrap_or_default().to_string_lossy();
+            let name = if tile_name == "tile0" {
+                gt_name.to_string()
+            } else {
+                format!("{}-{}", tile_name, gt_name)
+            };
+            gts.push(XeGt {
+                name,
+                path: gt.clone(),
+            });
+        }
+    }
+    gts
+}
+
+fn sorted_entries(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(prefix)
+                .is_some_and(|index| index.parse::<u32>().is_ok())
+        })
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Active and highest level of a `pp_dpm_*` table, in MHz:
+///
+/// ```text
+/// 0: 500Mhz
+/// 1: 1800Mhz *
+/// ```
+fn read_dpm(path: &Path) -> Option<(f64, f64)> {
+    let text = fs::read_to_string(path).ok()?;
+    let mut current = None;
+    let mut max = None;
+    for line in text.lines() {
+        let Some((_, level)) = line.split_once(':') else {
+            continue;
+        };
+        let mhz: f64 = level
+            .trim()
+            .trim_end_matches('*')
+            .trim()
+            .to_lowercase()
+            .trim_end_matches("mhz")
+            .parse()
+            .ok()?;
+        if line.trim_end().ends_with('*') {
+            current = Some(mhz);
+        }
+        max = Some(max.map_or(mhz, |max: f64| max.max(mhz)));
+    }
+    Some((current?, max?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_dpm, GpuSource};
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, SensorSource},
+    };
+
+    #[test]
+    fn dpm_tables() {
+        let sysfs = Fixture::new();
+        let dpm = |text: &str| {
+            sysfs.write("pp_dpm_sclk", text);
+            read_dpm(&sysfs.path().join("pp_dpm_sclk"))
+        };
+        assert_eq!(
+            dpm("0: 500Mhz\n1: 1800Mhz *\n2: 2615Mhz\n"),
+            Some((1800.0, 2615.0))
+        );
+        // Some kernels print MHz and no space before the marker.
+        assert_eq!(dpm("0: 96MHz*\n1: 456MHz\n"), Some((96.0, 456.0)));
+        // Only the deep sleep level, with an `S:` index.
+        assert_eq!(dpm("S: 19Mhz *\n"), Some((19.0, 19.0)));
+        // No active level: the card is suspended or the table is stale.
+        assert_eq!(dpm("0: 500Mhz\n1: 1800Mhz\n"), None);
+        assert_eq!(dpm("0: 500Mhz *\n1: fast\n"), None);
+        assert_eq!(dpm(""), None);
+        assert_eq!(read_dpm(&sysfs.path().join("missing")), None);
+    }
+
+    #[test]
+    fn reads_amdgpu_cards() {
+        let sysfs = Fixture::new();
+        let device = "devices/pci0000:00/0000:03:00.0";
+        sysfs
+            .write(&format!("{}/vendor", device), "0x1002\n")
+            .write(&format!("{}/device", device), "0x73bf\n")
+            .write(&format!("{}/vbios_version", device), "113-D4120100-100\n")
+            .write(&format!("{}/gpu_busy_percent", device), "37\n")
+            .write(&format!("{}/mem_busy_percent", device), "")
+            .write(
+                &format!("{}/pp_dpm_sclk", device),
+                "0: 500Mhz\n1: 2310Mhz *\n",
+            )
+            .write(
+                &format!("{}/pp_dpm_mclk", device),
+                "0: 96Mhz *\n1: 1000Mhz\n",
+            )
+            .link(&format!("{}/driver", device), "bus/pci/drivers/amdgpu")
+            .link("class/drm/card0/device", device)
+            .link("class/drm/card0-DP-1/device", device)
+            .link(&format!("{}/drm/card0", device), "class/drm/card0");
+
+        let sensors = GpuSource::new(sysfs.path()).read_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "amdgpu/0000:03:00.0/gpu_busy",
+                "amdgpu/0000:03:00.0/mem_busy",
+                "amdgpu/0000:03:00.0/sclk",
+                "amdgpu/0000:03:00.0/mclk",
+            ]
+        );
+        assert_eq!(sensors[0].label, "card0 busy");
+        assert_eq!(sensors[0].value, Some(37.0));
+        // A card in runtime suspend keeps the channel with a missing sample.
+        assert_eq!(sensors[1].value, None);
+        assert!(sensors[2].kind == ChannelKind::Frequency);
+        assert_eq!(
+            (sensors[2].value, sensors[2].max),
+            (Some(2310.0), Some(2310.0))
+        );
+        assert_eq!(
+            (sensors[3].value, sensors[3].max),
+            (Some(96.0), Some(1000.0))
+        );
+
+        let identity = sensors[0].identity.as_ref().unwrap();
+        assert_eq!(identity.model.as_deref(), Some("1002:73bf"));
+        assert_eq!(identity.firmware.as_deref(), Some("113-D4120100-100"));
+    }
+
+    #[test]
+    fn rediscovers_when_cards_change() {
+        let sysfs = Fixture::new();
+        let intel = "devices/pci0000:00/0000:00:02.0";
+        sysfs
+            .write(
+                &format!("{}/vendor", intel),
+                "0x8086
+",
+            )
+            .write(
+                &format!("{}/device", intel),
+                "0xa780
+",
+            )
+            .write(
+                "class/drm/card0/gt_cur_freq_mhz",
+                "350
+",
+            )
+            .write(
+                "class/drm/card0/gt_RP0_freq_mhz",
+                "1550
+",
+            )
+            .link(&format!("{}/driver", intel), "bus/pci/drivers/i915")
+            .link("class/drm/card0/device", intel)
+            .link(&format!("{}/drm/card0", intel), "class/drm/card0");
+
+        let mut source = GpuSource::new(sysfs.path());
+        let sensors = source.read_sensors().unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].id, "i915/0000:00:02.0/gt_freq");
+        assert_eq!(
+            (sensors[0].value, sensors[0].max),
+            (Some(350.0), Some(1550.0))
+        );
+
+        // Later frames re-read only the files found at discovery.
+        sysfs.write(
+            "class/drm/card0/gt_cur_freq_mhz",
+            "1200
+",
+        );
+        sysfs.write(
+            &format!("{}/gpu_busy_percent", intel),
+            "5
+",
+        );
+        let sensors = source.read_sensors().unwrap();
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].value, Some(1200.0));
+
+        let amd = "devices/pci0000:00/0000:03:00.0";
+        sysfs
+            .write(
+                &format!("{}/gpu_busy_percent", amd),
+                "80
+",
+            )
+            .link(&format!("{}/driver", amd), "bus/pci/drivers/amdgpu")
+            .link("class/drm/card1/device", amd)
+            .link(&format!("{}/drm/card1", amd), "class/drm/card1");
+        let sensors = source.read_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "i915/0000:00:02.0/gpu_busy",
+                "i915/0000:00:02.0/gt_freq",
+                "amdgpu/0000:03:00.0/gpu_busy",
+            ]
+        );
+        assert_eq!(sensors[2].label, "card1 busy");
+
+        sysfs.remove("class/drm/card1");
+        assert_eq!(source.read_sensors().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,422 @@
+use super::{
+    gpu, sensor_id, storage, ChannelKind, Classifier, DeviceKind, LmSensorsConfig, SensorLabel,
+    SensorSource,
+};
+use crate::{
+    error::{self, Error},
+    expr::Expr,
+};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+};
+
+/// hwmon channels under `/sys/class/hwmon`. Chips are discovered once and
+/// again when the hwmon listing changes or a device goes away; in between,
+/// each frame only re-reads the open `_input` and alarm files in place.
+pub struct HwmonSource {
+    root: PathBuf,
+    classifier: Classifier,
+    lm_sensors: LmSensorsConfig,
+    /// `hwmonN` entries seen at the last discovery, sorted.
+    listing: Option<Vec<OsString>>,
+    channels: Vec<HwmonChannel>,
+    /// A device vanished mid-read; discover again on the next frame.
+    stale: bool,
+}
+
+/// A discovered channel: everything but the value and alarm state is read
+/// once, at discovery.
+struct HwmonChannel {
+    template: SensorLabel,
+    input: File,
+    alarms: Vec<File>,
+    divisor: f64,
+    compute: Option<Expr>,
+}
+
+impl HwmonSource {
+    pub fn new(sysfs_root: &Path, classifier: Classifier, lm_sensors: LmSensorsConfig) -> Self {
+        HwmonSource {
+            root: sysfs_root.join("class").join("hwmon"),
+            classifier,
+            lm_sensors,
+            listing: None,
+            channels: Vec::new(),
+            stale: false,
+        }
+    }
+}
+
+impl SensorSource for HwmonSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        if !self.root.exists() {
+            // Kernels without hwmon still have thermal zones to fall back on.
+            return Ok(Vec::new());
+        }
+
+        let listing = list_hwmon(&self.root)?;
+        if self.stale || self.listing.as_ref() != Some(&listing) {
+            self.channels = discover(&self.root, &self.classifier, &self.lm_sensors)?;
+            self.listing = Some(listing);
+            self.stale = false;
+        }
+
+        let mut collected_data = Vec::with_capacity(self.channels.len());
+        for channel in &self.channels {
+            let mut sensor = channel.template.clone();
+            match read_raw(&channel.input) {
+                Ok(raw) => {
+                    let value = raw as f64 / channel.divisor;
+                    sensor.value = match &channel.compute {
+                        Some(expr) => expr.eval(&|name| (name == "@").then_some(value)),
+                        None => Some(value),
+                    };
+                }
+                // The device was unbound or unplugged.
+                Err(e) if e.kind() == io::ErrorKind::NotFound || e.raw_os_error() == Some(19) => {
+                    self.stale = true;
+                }
+                // Suspended devices fail the read (EIO, ENODATA); the
+                // channel stays in the frame with a missing sample.
+                Err(_) => {}
+            }
+            if !channel.alarms.is_empty() {
+                sensor.alarm = Some(
+                    channel
+                        .alarms
+                        .iter()
+                        .any(|alarm| read_raw(alarm).is_ok_and(|flag| flag != 0)),
+                );
+            }
+            collected_data.push(sensor);
+        }
+        Ok(collected_data)
+    }
+}
+
+fn list_hwmon(hwmon_dir: &Path) -> error::Result<Vec<OsString>> {
+    let mut names: Vec<OsString> = fs::read_dir(hwmon_dir)
+        .map_err(|source| Error::Discovery {
+            path: hwmon_dir.to_path_buf(),
+            source,
+        })?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .filter(|name| name.to_string_lossy().starts_with("hwmon"))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Opens every hwmon channel, applying the lm-sensors `label`, `ignore` and
+/// `compute` statements for its chip.
+fn discover(
+    hwmon_dir: &Path,
+    classifier: &Classifier,
+    lm_sensors: &LmSensorsConfig,
+) -> error::Result<Vec<HwmonChannel>> {
+    let mut channels = Vec::new();
+
+    let hwmon_paths = list_hwmon(hwmon_dir)?
+        .into_iter()
+        .map(|name| hwmon_dir.join(name))
+        .filter(|path| path.is_dir());
+
+    for path in hwmon_paths {
+        // Without a name the chip has no stable id; leave it out.
+        let Ok(device_name) = fs::read_to_string(path.join("name")) else {
+            continue;
+        };
+        let device_name = device_name.trim().to_string();
+        let device = classifier.classify(&device_name);
+        let address = device_address(&path);
+        let rules = lm_sensors.chip(&device_name, &address);
+        let identity = match device {
+            DeviceKind::Storage => storage::drive_identity(&path),
+            DeviceKind::Gpu => fs::canonicalize(path.join("device"))
+                .ok()
+                .and_then(|device| gpu::card_identity(&device)),
+            _ => None,
+        };
+
+        let Ok(entries) = fs::read_dir(&path) else {
+            continue;
+        };
+        let mut files: Vec<String> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+
+        for file_name in &files {
+            // PWM outputs are read from the bare `pwmN` attribute.
+            let is_pwm = file_name
+                .strip_prefix("pwm")
+                .is_some_and(|index| index.parse::<u32>().is_ok());
+            let Some(channel) = file_name
+                .strip_suffix("_input")
+                .or_else(|| file_name.strip_suffix("_average"))
+                .or_else(|| is_pwm.then_some(file_name.as_str()))
+            else {
+                continue;
+            };
+            let Some(kind) = ChannelKind::from_channel(channel) else {
+                continue;
+            };
+            // Power channels may expose both; prefer the driver's average.
+            let is_average = file_name.ends_with("_average");
+            let skip = match kind {
+                ChannelKind::Power => {
+                    !is_average && files.contains(&format!("{}_average", channel))
+                }
+                _ => is_average,
+            };
+            if skip || rules.ignored(channel) {
+                continue;
+            }
+            let Ok(input) = File::open(path.join(file_name)) else {
+                continue;
+            };
+
+            let divisor = kind.sysfs_divisor();
+            let label_string = fs::read_to_string(path.join(format!("{}_label", channel)))
+                .map(|label| label.trim().to_string())
+                .unwrap_or_else(|_| channel.to_string());
+            // Like libsensors, compute applies to the limits as well.
+            let compute = rules.compute(channel).cloned();
+            let attribute = |name: &str| {
+                let raw: i64 = fs::read_to_string(path.join(format!("{}_{}", channel, name)))
+                    .ok()?
+                    .trim()
+                    .parse()
+                    .ok()?;
+                let value = raw as f64 / divisor;
+                match &compute {
+                    Some(expr) => expr.eval(&|name| (name == "@").then_some(value)),
+                    None => Some(value),
+                }
+            };
+            let alarms: Vec<File> = ["alarm", "max_alarm", "crit_alarm"]
+                .iter()
+                .filter_map(|flag| File::open(path.join(format!("{}_{}", channel, flag))).ok())
+                .collect();
+
+            let template = SensorLabel {
+                id: sensor_id(&[&device_name, &address, channel]),
+                label: label_string,
+                lm_label: rules.label(channel).map(str::to_string),
+                chip: device_name.clone(),
+                kind,
+                device,
+                value: None,
+                min: attribute("min"),
+                max: attribute("max"),
+                crit: attribute("crit"),
+                crit_hyst: attribute("crit_hyst"),
+                identity: identity.clone(),
+                ..Default::default()
+            };
+            channels.push(HwmonChannel {
+                template,
+                input,
+                alarms,
+                divisor,
+                compute,
+            });
+        }
+    }
+
+    Ok(channels)
+}
+
+/// Re-reads an open sysfs attribute from offset 0; sysfs regenerates the
+/// value on every positioned read, so the file never needs reopening.
+fn read_raw(file: &File) -> io::Result<i64> {
+    let mut buf = [0u8; 32];
+    let len = file.read_at(&mut buf, 0)?;
+    std::str::from_utf8(&buf[..len])
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an integer"))
+}
+
+/// Bus address of the device behind an hwmon directory: the SCSI address of
+/// a disk (`0:0:0:0`, several share one controller), else the nearest PCI
+/// address in its device path (`0000:03:00.0`), otherwise the device name
+/// (`coretemp.0`), or `virtual` for hwmon chips without a parent device.
+fn device_address(hwmon_path: &Path) -> String {
+    let link = hwmon_path.join("device");
+    let Some(target) = fs::canonicalize(&link)
+        .ok()
+        .or_else(|| fs::read_link(&link).ok())
+    else {
+        return "virtual".to_string();
+    };
+
+    let names: Vec<String> = target
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if let Some(scsi) = names.last().filter(|name| is_scsi_address(name)) {
+        return scsi.clone();
+    }
+    names
+        .iter()
+        .rev()
+        .find(|name| is_pci_address(name))
+        .or_else(|| names.last())
+        .cloned()
+        .unwrap_or_else(|| "virtual".to_string())
+}
+
+fn is_pci_address(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && name
+            .chars()
+            .enumerate()
+            .all(|(i, c)| [4, 7, 10].contains(&i) || c.is_ascii_hexdigit())
+}
+
+/// `host:channel:id:lun`, as SCSI disks are named in sysfs.
+fn is_scsi_address(name: &str) -> bool {
+    let parts: Vec<&str> = name.split(':').collect();
+    parts.len() == 4
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HwmonSource;
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, Classifier, DeviceKind, LmSensorsConfig, SensorSource},
+    };
+
+    /// A k10temp chip on the PCI function a Ryzen data fabric uses.
+    fn k10temp(sysfs: &Fixture) {
+        sysfs
+            .write("class/hwmon/hwmon0/name", "k10temp\n")
+            .write("class/hwmon/hwmon0/temp1_input", "55375\n")
+            .write("class/hwmon/hwmon0/temp1_label", "Tctl\n")
+            .write("class/hwmon/hwmon0/temp3_input", "48125\n")
+            .write("class/hwmon/hwmon0/temp3_label", "Tccd1\n")
+            .write("class/hwmon/hwmon0/temp3_max", "80000\n")
+            .write("class/hwmon/hwmon0/temp3_crit", "95000\n")
+            .write("class/hwmon/hwmon0/temp3_alarm", "0\n")
+            .link(
+                "class/hwmon/hwmon0/device",
+                "devices/pci0000:00/0000:00:18.3",
+            );
+    }
+
+    fn source(sysfs: &Fixture, lm_sensors: LmSensorsConfig) -> HwmonSource {
+        HwmonSource::new(sysfs.path(), Classifier::new(Vec::new()), lm_sensors)
+    }
+
+    #[test]
+    fn reads_channels_with_stable_ids() {
+        let sysfs = Fixture::new();
+        k10temp(&sysfs);
+        sysfs
+            .write("class/hwmon/hwmon1/name", "nct6798\n")
+            .write("class/hwmon/hwmon1/fan2_input", "1180\n")
+            .write("class/hwmon/hwmon1/fan2_min", "300\n")
+            .write("class/hwmon/hwmon1/in0_input", "1032\n")
+            .write("class/hwmon/hwmon1/pwm2", "128\n")
+            .link("class/hwmon/hwmon1/device", "devices/platform/nct6775.656");
+
+        let sensors = source(&sysfs, LmSensorsConfig::default())
+            .read_sensors()
+            .unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "k10temp/0000:00:18.3/temp1",
+                "k10temp/0000:00:18.3/temp3",
+                "nct6798/nct6775.656/fan2",
+                "nct6798/nct6775.656/in0",
+                "nct6798/nct6775.656/pwm2",
+            ]
+        );
+
+        let tccd = &sensors[1];
+        assert_eq!(tccd.label, "Tccd1");
+        assert!(matches!(tccd.device, DeviceKind::Cpu));
+        assert_eq!(tccd.value, Some(48.125));
+        assert_eq!((tccd.max, tccd.crit), (Some(80.0), Some(95.0)));
+        assert_eq!(tccd.alarm, Some(false));
+
+        let kinds: Vec<ChannelKind> = sensors[2..].iter().map(|s| s.kind).collect();
+        assert!(kinds == [ChannelKind::Fan, ChannelKind::Voltage, ChannelKind::Pwm]);
+        assert_eq!(sensors[2].min, Some(300.0));
+        assert_eq!(sensors[3].value, Some(1.032));
+        assert_eq!(sensors[4].value.map(f64::round), Some(50.0));
+    }
+
+    #[test]
+    fn failed_reads_are_missing_samples() {
+        let sysfs = Fixture::new();
+        k10temp(&sysfs);
+        let mut source = source(&sysfs, LmSensorsConfig::default());
+        source.read_sensors().unwrap();
+
+        sysfs.write("class/hwmon/hwmon0/temp1_input", "");
+        let sensors = source.read_sensors().unwrap();
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].value, None);
+        assert_eq!(sensors[1].value, Some(48.125));
+    }
+
+    #[test]
+    fn rediscovers_when_chips_come_and_go() {
+        let sysfs = Fixture::new();
+        k10temp(&sysfs);
+        let mut source = source(&sysfs, LmSensorsConfig::default());
+        assert_eq!(source.read_sensors().unwrap().len(), 2);
+
+        sysfs
+            .write("class/hwmon/hwmon2/name", "nvme\n")
+            .write("class/hwmon/hwmon2/temp1_input", "38850\n")
+            .link(
+                "class/hwmon/hwmon2/device",
+                "devices/pci0000:00/0000:03:00.0",
+            );
+        let sensors = source.read_sensors().unwrap();
+        assert_eq!(sensors.len(), 3);
+        assert_eq!(sensors[2].id, "nvme/0000:03:00.0/temp1");
+        assert!(matches!(sensors[2].device, DeviceKind::Storage));
+
+        sysfs.remove("class/hwmon/hwmon2");
+        assert_eq!(source.read_sensors().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn applies_lm_sensors_statements() {
+        let sysfs = Fixture::new();
+        k10temp(&sysfs);
+        sysfs.write(
+            "etc/sensors3.conf",
+            "chip \"k10temp-pci-00c3\"\n  label temp1 \"CPU\"\n  compute temp3 @-10, @+10\n",
+        );
+        let lm_sensors =
+            LmSensorsConfig::load(&[sysfs.path().join("etc/sensors3.conf")], true).unwrap();
+
+        let sensors = source(&sysfs, lm_sensors).read_sensors().unwrap();
+        // The driver label stays for topology placement.
+        assert_eq!(sensors[0].label, "Tctl");
+        assert_eq!(sensors[0].lm_label.as_deref(), Some("CPU"));
+        assert_eq!(sensors[1].value, Some(38.125));
+        assert_eq!(sensors[1].max, Some(70.0));
+    }
+}
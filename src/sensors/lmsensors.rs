@@ -0,0 +1,521 @@
+use crate::expr::Expr;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// `label`, `ignore` and `compute` statements from lm-sensors config files
+/// (`/etc/sensors3.conf`, `/etc/sensors.d/*`), grouped by `chip` block.
+/// Other statements (`set`, `bus`) do not affect readings and are skipped.
+#[derive(Clone, Default)]
+pub struct LmSensorsConfig {
+    blocks: Vec<ChipBlock>,
+}
+
+#[derive(Clone)]
+struct ChipBlock {
+    patterns: Vec<ChipName>,
+    labels: Vec<(String, String)>,
+    ignores: Vec<String>,
+    /// Feature and the expression turning the raw value into the shown one.
+    computes: Vec<(String, Expr)>,
+}
+
+/// A libsensors chip name, `prefix-bus-address`; `None` parts are `*`.
+#[derive(Clone)]
+struct ChipName {
+    prefix: Option<String>,
+    bus: Option<String>,
+    /// i2c adapter number, for `i2c-N` buses.
+    bus_number: Option<u32>,
+    address: Option<u32>,
+}
+
+impl LmSensorsConfig {
+    /// The files `sensors` reads: `/etc/sensors3.conf` (or the older
+    /// `/etc/sensors.conf`) and everything in `/etc/sensors.d`.
+    pub fn default_paths() -> Vec<PathBuf> {
+        let etc = Path::new("/etc");
+        let main = [etc.join("sensors3.conf"), etc.join("sensors.conf")]
+            .into_iter()
+            .find(|path| path.exists());
+        main.into_iter().chain([etc.join("sensors.d")]).collect()
+    }
+
+    /// Loads files and directories in order; later files take precedence.
+    /// Missing paths are skipped unless `required`.
+    pub fn load(paths: &[PathBuf], required: bool) -> io::Result<Self> {
+        let mut config = LmSensorsConfig::default();
+        for path in paths {
+            let files = if path.is_dir() {
+                let mut files: Vec<PathBuf> = fs::read_dir(path)
+                    .map_err(|e| with_path(e, path))?
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|file| {
+                        file.is_file()
+                            && !file
+                                .file_name()
+                                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+                    })
+                    .collect();
+                files.sort();
+                files
+            } else if path.exists() || required {
+                vec![path.clone()]
+            } else {
+                Vec::new()
+            };
+
+            for file in files {
+                let text = fs::read_to_string(&file).map_err(|e| with_path(e, &file))?;
+                let parsed = parse(&text).map_err(|e| with_path(e, &file))?;
+                config.blocks.extend(parsed.blocks);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The statements that apply to one hwmon chip, latest block first.
+    pub fn chip<'a>(&'a self, name: &str, address: &str) -> ChipRules<'a> {
+        let chip = ChipName::of_hwmon(name, address);
+        ChipRules {
+            blocks: self
+                .blocks
+                .iter()
+                .rev()
+                .filter(|block| block.patterns.iter().any(|p| p.matches(&chip)))
+                .collect(),
+        }
+    }
+}
+
+pub struct ChipRules<'a> {
+    blocks: Vec<&'a ChipBlock>,
+}
+
+impl ChipRules<'_> {
+    pub fn label(&self, feature: &str) -> Option<&str> {
+        self.blocks.iter().find_map(|block| {
+            block
+                .labels
+                .iter()
+                .rev()
+                .find(|(name, _)| name == feature)
+                .map(|(_, label)| label.as_str())
+        })
+    }
+
+    pub fn ignored(&self, feature: &str) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| block.ignores.iter().any(|name| name == feature))
+    }
+
+    pub fn compute(&self, feature: &str) -> Option<&Expr> {
+        self.blocks.iter().find_map(|block| {
+            block
+                .computes
+                .iter()
+                .rev()
+                .find(|(name, _)| name == feature)
+                .map(|(_, expr)| expr)
+        })
+    }
+}
+
+impl ChipName {
+    /// Parses `k10temp-pci-00c3`, `nct6798-isa-*`, `*-i2c-1-4c`,
+    /// `drivetemp-scsi-0-0` or `*`.
+    fn parse(pattern: &str) -> Option<ChipName> {
+        let wild = |part: &str| (part != "*").then(|| part.to_lowercase());
+        let hex = |part: &str| -> Option<Option<u32>> {
+            match part {
+                "*" => Some(None),
+                _ => u32::from_str_radix(part, 16).ok().map(Some),
+            }
+        };
+
+        let parts: Vec<&str> = pattern.split('-').collect();
+        let any = ChipName {
+            prefix: wild(parts[0]),
+            bus: None,
+            bus_number: None,
+            address: None,
+        };
+        match parts.len() {
+            1 => Some(any),
+            2 if parts[1] == "*" => Some(any),
+            2 => None,
+            n if n >= 4 && ["i2c", "scsi"].contains(&parts[n - 3]) => Some(ChipName {
+                prefix: wild(&parts[..n - 3].join("-")),
+                bus: Some(parts[n - 3].to_string()),
+                bus_number: match parts[n - 2] {
+                    "*" => None,
+                    number => Some(number.parse().ok()?),
+                },
+                address: hex(parts[n - 1])?,
+            }),
+            n => Some(ChipName {
+                prefix: wild(&parts[..n - 2].join("-")),
+                bus: wild(parts[n - 2]),
+                bus_number: None,
+                address: hex(parts[n - 1])?,
+            }),
+        }
+    }
+
+    /// The name libsensors gives an hwmon chip, from its driver name and
+    /// the device address twatch puts in sensor IDs.
+    fn of_hwmon(name: &str, address: &str) -> ChipName {
+        let chip = |bus: &str, bus_number, address| ChipName {
+            prefix: Some(name.to_lowercase()),
+            bus: Some(bus.to_string()),
+            bus_number,
+            address: Some(address),
+        };
+
+        if let Some(pci) = parse_pci(address) {
+            return chip("pci", None, pci);
+        }
+        if address == "virtual" {
+            return chip("virtual", None, 0);
+        }
+        // Platform devices: nct6775.656, coretemp.0.
+        if let Some(index) = address
+            .rsplit_once('.')
+            .and_then(|(_, index)| index.parse().ok())
+        {
+            return chip("isa", None, index);
+        }
+        // SCSI disks: host:channel:id:lun, named by host and id.
+        let scsi: Vec<u32> = address.split(':').filter_map(|p| p.parse().ok()).collect();
+        if let [host, _, id, _] = scsi[..] {
+            return chip("scsi", Some(host), id);
+        }
+        // i2c clients: 1-004c.
+        if let Some((adapter, client)) = address.split_once('-') {
+            if let (Ok(adapter), Ok(client)) = (adapter.parse(), u32::from_str_radix(client, 16)) {
+                return chip("i2c", Some(adapter), client);
+            }
+        }
+        ChipName {
+            prefix: Some(name.to_lowercase()),
+            bus: None,
+            bus_number: None,
+            address: None,
+        }
+    }
+
+    fn matches(&self, chip: &ChipName) -> bool {
+        let part = |pattern: &Option<String>, value: &Option<String>| {
+            pattern.is_none() || pattern == value
+        };
+        let number =
+            |pattern: Option<u32>, value: Option<u32>| pattern.is_none() || pattern == value;
+        part(&self.prefix, &chip.prefix)
+            && part(&self.bus, &chip.bus)
+            && number(self.bus_number, chip.bus_number)
+            && number(self.address, chip.address)
+    }
+}
+
+/// `0000:00:18.3` as libsensors numbers it: domain, bus, device, function.
+fn parse_pci(address: &str) -> Option<u32> {
+    let (domain, rest) = address.split_once(':')?;
+    let (bus, rest) = rest.split_once(':')?;
+    let (device, function) = rest.split_once('.')?;
+    let field = |text: &str| u32::from_str_radix(text, 16).ok();
+    Some((field(domain)? << 16) + (field(bus)? << 8) + (field(device)? << 3) + field(function)?)
+}
+
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
+
+fn invalid(line: usize, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+/// Splits a statement into words; double quotes group words, `#` starts a
+/// comment outside quotes.
+fn words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '#' {
+            break;
+        } else if c == '"' {
+            chars.next();
+            let mut word = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => word.extend(chars.next()),
+                    Some(c) => word.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            words.push(word);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '#' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+fn parse(text: &str) -> io::Result<LmSensorsConfig> {
+    let mut config = LmSensorsConfig::default();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let words = words(raw).map_err(|e| invalid(line, e))?;
+        let Some(keyword) = words.first() else {
+            continue;
+        };
+
+        if keyword == "chip" {
+            let patterns = words[1..]
+                .iter()
+                .map(|word| {
+                    ChipName::parse(word)
+                        .ok_or_else(|| invalid(line, format!("bad chip name '{}'", word)))
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            if patterns.is_empty() {
+                return Err(invalid(line, "'chip' needs a chip name".to_string()));
+            }
+            config.blocks.push(ChipBlock {
+                patterns,
+                labels: Vec::new(),
+                ignores: Vec::new(),
+                computes: Vec::new(),
+            });
+            continue;
+        }
+        if !["label", "ignore", "compute"].contains(&keyword.as_str()) {
+            continue;
+        }
+
+        let block = config
+            .blocks
+            .last_mut()
+            .ok_or_else(|| invalid(line, format!("'{}' before any 'chip' line", keyword)))?;
+        let feature = words
+            .get(1)
+            .ok_or_else(|| invalid(line, format!("'{}' needs a feature name", keyword)))?
+            .clone();
+        match keyword.as_str() {
+            "label" => {
+                let label = words
+                    .get(2)
+                    .ok_or_else(|| invalid(line, format!("'label {}' needs a label", feature)))?;
+                block.labels.push((feature, label.clone()));
+            }
+            "ignore" => block.ignores.push(feature),
+            _ => {
+                // compute <feature> <raw to shown>, <shown to raw>
+                let rest = raw
+                    .split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start()
+                    .trim_start_matches("compute")
+                    .trim_start()
+                    .trim_start_matches(feature.as_str());
+                let from_raw = rest.split(',').next().unwrap_or_default();
+                let expr = Expr::parse(from_raw)
+                    .map_err(|e| invalid(line, format!("compute {}: {}", feature, e)))?;
+                block.computes.push((feature, expr));
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChipName, LmSensorsConfig};
+    use crate::fixture::Fixture;
+
+    fn matches(pattern: &str, name: &str, address: &str) -> bool {
+        ChipName::parse(pattern)
+            .unwrap()
+            .matches(&ChipName::of_hwmon(name, address))
+    }
+
+    fn config(text: &str) -> LmSensorsConfig {
+        super::parse(text).unwrap()
+    }
+
+    fn parse_error(text: &str) -> String {
+        match super::parse(text) {
+            Ok(_) => panic!("parsed: {}", text),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn chip_names() {
+        let chip = ChipName::parse("k10temp-pci-00c3").unwrap();
+        assert_eq!(chip.prefix.as_deref(), Some("k10temp"));
+        assert_eq!(chip.bus.as_deref(), Some("pci"));
+        assert_eq!(chip.address, Some(0xc3));
+
+        let chip = ChipName::parse("*-i2c-1-4c").unwrap();
+        assert_eq!(chip.prefix, None);
+        assert_eq!(chip.bus.as_deref(), Some("i2c"));
+        assert_eq!(chip.bus_number, Some(1));
+        assert_eq!(chip.address, Some(0x4c));
+
+        let chip = ChipName::parse("nct6798-isa-*").unwrap();
+        assert_eq!(chip.address, None);
+
+        let chip = ChipName::parse("*").unwrap();
+        assert!(chip.prefix.is_none() && chip.bus.is_none());
+
+        assert!(ChipName::parse("k10temp-pci").is_none());
+        assert!(ChipName::parse("k10temp-pci-zz").is_none());
+        assert!(ChipName::parse("lm75-i2c-x-48").is_none());
+    }
+
+    #[test]
+    fn hwmon_chips_by_bus() {
+        // Device 18h function 3 on bus 0.
+        assert!(matches("k10temp-pci-00c3", "k10temp", "0000:00:18.3"));
+        assert!(!matches("k10temp-pci-00c4", "k10temp", "0000:00:18.3"));
+        // The platform device index is the ISA port: 656 = 0x290.
+        assert!(matches("nct6798-isa-0290", "nct6798", "nct6775.656"));
+        assert!(matches("coretemp-isa-0000", "coretemp", "coretemp.0"));
+        assert!(matches("drivetemp-scsi-1-2", "drivetemp", "1:0:2:0"));
+        assert!(!matches("drivetemp-scsi-0-2", "drivetemp", "1:0:2:0"));
+        assert!(matches("lm75-i2c-1-48", "lm75", "1-0048"));
+        assert!(matches("acpitz-virtual-0", "acpitz", "virtual"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("amdgpu-*", "amdgpu", "0000:0b:00.0"));
+        assert!(matches("nvme-pci-*", "nvme", "0000:03:00.0"));
+        assert!(!matches("nvme-isa-*", "nvme", "0000:03:00.0"));
+        assert!(matches("*-i2c-*-48", "lm75", "7-0048"));
+        assert!(!matches("*-i2c-*-49", "lm75", "7-0048"));
+        assert!(matches("*", "anything", "virtual"));
+        // Chip prefixes compare case-insensitively, like libsensors.
+        assert!(matches("NCT6798-isa-*", "nct6798", "nct6775.656"));
+    }
+
+    #[test]
+    fn statements() {
+        let config = config(
+            r#"
+            # comment
+            chip "k10temp-pci-*" "zenpower-pci-*"
+                label temp1 "CPU Tctl"   # trailing comment
+                label temp3 "CCD \"A\""
+                compute temp1 @-10, @+10
+                ignore temp4
+                set temp1_max 90
+
+            chip "k10temp-pci-00c3"
+                label temp1 "Tctl (node 0)"
+            "#,
+        );
+
+        let rules = config.chip("k10temp", "0000:00:18.3");
+        // A later block wins over an earlier one.
+        assert_eq!(rules.label("temp1"), Some("Tctl (node 0)"));
+        assert_eq!(rules.label("temp3"), Some("CCD \"A\""));
+        assert_eq!(rules.label("temp2"), None);
+        assert!(rules.ignored("temp4"));
+        assert!(!rules.ignored("temp1"));
+        let compute = rules.compute("temp1").unwrap();
+        assert_eq!(
+            compute.eval(&|name| (name == "@").then_some(60.0)),
+            Some(50.0)
+        );
+
+        let zen = config.chip("zenpower", "0000:00:18.3");
+        assert_eq!(zen.label("temp1"), Some("CPU Tctl"));
+        let other = config.chip("nct6798", "nct6775.656");
+        assert_eq!(other.label("temp1"), None);
+        assert!(other.compute("temp1").is_none());
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            parse_error("label temp1 \"x\""),
+            "line 1: 'label' before any 'chip' line"
+        );
+        assert_eq!(
+            parse_error("chip \"k10temp-*\"\n  label temp1 \"open"),
+            "line 2: unterminated string"
+        );
+        assert_eq!(
+            parse_error("chip \"k10temp-pci\""),
+            "line 1: bad chip name 'k10temp-pci'"
+        );
+        assert_eq!(parse_error("chip"), "line 1: 'chip' needs a chip name");
+        assert_eq!(
+            parse_error("chip \"*\"\n\n  compute temp1 @ +, @"),
+            "line 3: compute temp1: expression ends too early"
+        );
+        assert_eq!(
+            parse_error("chip \"*\"\n  label temp1"),
+            "line 2: 'label temp1' needs a label"
+        );
+    }
+
+    #[test]
+    fn loads_files_and_sensors_d() {
+        let etc = Fixture::new();
+        etc.write(
+            "sensors3.conf",
+            "chip \"k10temp-*\"\n  label temp1 \"main\"\n",
+        )
+        .write(
+            "sensors.d/50-board.conf",
+            "chip \"k10temp-*\"\n  ignore temp3\n",
+        )
+        .write(
+            "sensors.d/90-local.conf",
+            "chip \"k10temp-*\"\n  label temp1 \"local\"\n",
+        )
+        .write("sensors.d/.hidden", "not a config");
+        let paths = [
+            etc.path().join("sensors3.conf"),
+            etc.path().join("sensors.d"),
+            etc.path().join("missing.conf"),
+        ];
+
+        let config = LmSensorsConfig::load(&paths, false).unwrap();
+        let rules = config.chip("k10temp", "0000:00:18.3");
+        // sensors.d is read after the main file, in name order.
+        assert_eq!(rules.label("temp1"), Some("local"));
+        assert!(rules.ignored("temp3"));
+
+        let error = LmSensorsConfig::load(&paths, true).err().unwrap();
+        assert!(error.to_string().contains("missing.conf"));
+
+        etc.write("sensors.d/95-broken.conf", "ignore temp1\n");
+        let error = LmSensorsConfig::load(&paths[..2], false).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("95-broken.conf: line 1: 'ignore' before any 'chip' line"));
+    }
+}
@@ -0,0 +1,383 @@
+use super::{sensor_id, ChannelKind, DeviceIdentity, DeviceKind, SensorLabel, SensorSource};
+use crate::error;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+/// Fields asked of `nvidia-smi --query-gpu`, in output column order.
+const QUERY: &[&str] = &[
+    "index",
+    "pci.bus_id",
+    "name",
+    "serial",
+    "vbios_version",
+    "temperature.gpu",
+    "temperature.memory",
+    "power.draw",
+    "power.limit",
+    "clocks.gr",
+    "clocks.max.gr",
+    "clocks.mem",
+    "clocks.max.mem",
+    "utilization.gpu",
+    "utilization.memory",
+    "fan.speed",
+];
+
+/// How often nvidia-smi is run, whatever the frame delay.
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// How long the first frame waits for the first run, so NVIDIA channels
+/// are there from the start rather than appearing a frame later.
+const FIRST_RUN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// NVIDIA GPUs on the proprietary driver, which has no hwmon chip, read by
+/// running `nvidia-smi --query-gpu=... --format=csv,noheader,nounits`.
+/// A run takes tens of milliseconds, and more without persistence mode, so
+/// it happens on a thread of its own every [`REFRESH`]; frames get the
+/// latest result without waiting, and a hung nvidia-smi only stops the
+/// values from updating.
+pub struct NvidiaSmiSource {
+    /// `None` when NVIDIA GPUs are left out, and once the command turns
+    /// out not to exist, so machines without them do not try to spawn it
+    /// again.
+    command: Option<PathBuf>,
+    /// Results of the background runs; `None` until the first frame.
+    results: Option<Receiver<Vec<SensorLabel>>>,
+    latest: Vec<SensorLabel>,
+}
+
+impl NvidiaSmiSource {
+    pub fn new(command: Option<PathBuf>) -> Self {
+        NvidiaSmiSource {
+            command,
+            results: None,
+            latest: Vec::new(),
+        }
+    }
+}
+
+impl SensorSource for NvidiaSmiSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let Some(command) = &self.command else {
+            return Ok(Vec::new());
+        };
+        let results = match &self.results {
+            Some(results) => results,
+            None => {
+                let results = spawn_queries(command.clone());
+                if let Ok(sensors) = results.recv_timeout(FIRST_RUN_TIMEOUT) {
+                    self.latest = sensors;
+                }
+                self.results.insert(results)
+            }
+        };
+        loop {
+            match results.try_recv() {
+                Ok(sensors) => self.latest = sensors,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.command = None;
+                    self.results = None;
+                    self.latest.clear();
+                    break;
+                }
+            }
+        }
+        Ok(self.latest.clone())
+    }
+}
+
+/// Runs nvidia-smi every [`REFRESH`] on a new thread, until the command
+/// turns out not to exist or the receiver is dropped.
+fn spawn_queries(command: PathBuf) -> Receiver<Vec<SensorLabel>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        let Some(sensors) = query(&command) else {
+            return;
+        };
+        if sender.send(sensors).is_err() {
+            return;
+        }
+        thread::sleep(REFRESH);
+    });
+    receiver
+}
+
+/// One nvidia-smi run; `None` when the command does not exist.
+fn query(command: &Path) -> Option<Vec<SensorLabel>> {
+    let output = Command::new(command)
+        .arg(format!("--query-gpu={}", QUERY.join(",")))
+        .arg("--format=csv,noheader,nounits")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(_) => return Some(Vec::new()),
+    };
+    // No driver loaded or no GPU found; the GPUs simply go offline.
+    if !output.status.success() {
+        return Some(Vec::new());
+    }
+
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .flat_map(gpu_channels)
+            .collect(),
+    )
+}
+
+/// The channels of one GPU's CSV row; empty when the row is malformed.
+fn gpu_channels(line: &str) -> Vec<SensorLabel> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() != QUERY.len() {
+        return Vec::new();
+    }
+    let field = |name: &str| {
+        let index = QUERY.iter().position(|q| *q == name)?;
+        let value = fields[index];
+        // Unsupported fields read "[N/A]" or "[Not Supported]".
+        (!value.is_empty() && !value.starts_with('[')).then_some(value)
+    };
+    let number = |name: &str| field(name)?.parse::<f64>().ok();
+
+    let (Some(index), Some(address)) = (field("index"), field("pci.bus_id").and_then(pci_address))
+    else {
+        return Vec::new();
+    };
+    let card_name = format!("nvidia{}", index);
+    let identity = DeviceIdentity {
+        name: Some(card_name.clone()),
+        model: field("name").map(str::to_string),
+        serial: field("serial").map(str::to_string),
+        firmware: field("vbios_version").map(str::to_string),
+    };
+
+    let channel = |name: &str, label: &str, kind, value, max| SensorLabel {
+        id: sensor_id(&["nvidia", &address, name]),
+        label: format!("{} {}", card_name, label),
+        chip: "nvidia".to_string(),
+        kind,
+        device: DeviceKind::Gpu,
+        value,
+        max,
+        identity: Some(identity.clone()),
+        ..Default::default()
+    };
+    // Columns a GPU does not support leave its channel out altogether.
+    let supported = |name: &str| field(name).is_some();
+
+    let mut channels = vec![channel(
+        "temp_gpu",
+        "GPU",
+        ChannelKind::Temperature,
+        number("temperature.gpu"),
+        None,
+    )];
+    if supported("temperature.memory") {
+        channels.push(channel(
+            "temp_mem",
+            "memory",
+            ChannelKind::Temperature,
+            number("temperature.memory"),
+            None,
+        ));
+    }
+    if supported("power.draw") {
+        channels.push(channel(
+            "power",
+            "power",
+            ChannelKind::Power,
+            number("power.draw"),
+            number("power.limit"),
+        ));
+    }
+    if supported("fan.speed") {
+        channels.push(channel(
+            "fan",
+            "fan",
+            ChannelKind::Pwm,
+            number("fan.speed"),
+            None,
+        ));
+    }
+    for (name, label, value, max) in [
+        ("gpu_busy", "busy", "utilization.gpu", None),
+        ("mem_busy", "memory busy", "utilization.memory", None),
+        ("sclk", "graphics clock", "clocks.gr", Some("clocks.max.gr")),
+        ("mclk", "memory clock", "clocks.mem", Some("clocks.max.mem")),
+    ] {
+        if !supported(value) {
+            continue;
+        }
+        let kind = if value.starts_with("clocks") {
+            ChannelKind::Frequency
+        } else {
+            ChannelKind::Utilization
+        };
+        channels.push(channel(
+            name,
+            label,
+            kind,
+            number(value),
+            max.and_then(number),
+        ));
+    }
+    channels
+}
+
+/// `00000000:01:00.0` as nvidia-smi prints it, in the `0000:01:00.0` form
+/// sysfs and the other sensor IDs use.
+fn pci_address(bus_id: &str) -> Option<String> {
+    let (domain, rest) = bus_id.split_once(':')?;
+    let domain = u32::from_str_radix(domain, 16).ok()?;
+    Some(format!("{:04x}:{}", domain, rest.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gpu_channels, pci_address, NvidiaSmiSource};
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, SensorSource},
+    };
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    const RTX: &str = "0, 00000000:01:00.0, NVIDIA GeForce RTX 3080, 1324021012345, \
+        94.02.42.00.a1, 61, [N/A], 212.45, 320.00, 1710, 2100, 9251, 9501, 87, 42, 55";
+
+    #[test]
+    fn bus_ids() {
+        assert_eq!(
+            pci_address("00000000:01:00.0").as_deref(),
+            Some("0000:01:00.0")
+        );
+        assert_eq!(
+            pci_address("00000001:0A:00.0").as_deref(),
+            Some("0001:0a:00.0")
+        );
+        assert_eq!(pci_address("[N/A]"), None);
+    }
+
+    #[test]
+    fn parses_a_gpu_row() {
+        let channels = gpu_channels(RTX);
+        let ids: Vec<&str> = channels.iter().map(|c| c.id.as_str()).collect();
+        // No memory temperature: the column read [N/A].
+        assert_eq!(
+            ids,
+            [
+                "nvidia/0000:01:00.0/temp_gpu",
+                "nvidia/0000:01:00.0/power",
+                "nvidia/0000:01:00.0/fan",
+                "nvidia/0000:01:00.0/gpu_busy",
+                "nvidia/0000:01:00.0/mem_busy",
+                "nvidia/0000:01:00.0/sclk",
+                "nvidia/0000:01:00.0/mclk",
+            ]
+        );
+        let labels: Vec<&str> = channels.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "nvidia0 GPU",
+                "nvidia0 power",
+                "nvidia0 fan",
+                "nvidia0 busy",
+                "nvidia0 memory busy",
+                "nvidia0 graphics clock",
+                "nvidia0 memory clock",
+            ]
+        );
+        assert_eq!(channels[0].value, Some(61.0));
+        assert_eq!(
+            (channels[1].value, channels[1].max),
+            (Some(212.45), Some(320.0))
+        );
+        assert!(channels[5].kind == ChannelKind::Frequency);
+        assert_eq!(channels[5].max, Some(2100.0));
+
+        let identity = channels[0].identity.as_ref().unwrap();
+        assert_eq!(identity.model.as_deref(), Some("NVIDIA GeForce RTX 3080"));
+        assert_eq!(identity.firmware.as_deref(), Some("94.02.42.00.a1"));
+    }
+
+    #[test]
+    fn skips_malformed_rows() {
+        assert!(gpu_channels("").is_empty());
+        assert!(gpu_channels("0, 00000000:01:00.0, NVIDIA GeForce RTX 3080").is_empty());
+        assert!(gpu_channels(&RTX.replacen("00000000:01:00.0", "[N/A]", 1)).is_empty());
+    }
+
+    #[test]
+    fn runs_the_command() {
+        let bin = Fixture::new();
+        bin.write(
+            "nvidia-smi",
+            &format!(
+                "#!/bin/sh\ncase \"$1\" in --query-gpu=index,pci.bus_id,*) ;; *) exit 2 ;; esac\n\
+                 echo '{}'\necho '{}'\n",
+                RTX,
+                RTX.replacen("0, 00000000:01", "1, 00000000:02", 1)
+            ),
+        );
+        let command = bin.path().join("nvidia-smi");
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let sensors = NvidiaSmiSource::new(Some(command)).read_sensors().unwrap();
+        assert_eq!(sensors.len(), 14);
+        assert_eq!(sensors[7].id, "nvidia/0000:02:00.0/temp_gpu");
+        assert_eq!(sensors[7].label, "nvidia1 GPU");
+    }
+
+    #[test]
+    fn failing_or_missing_commands_report_nothing() {
+        let bin = Fixture::new();
+        bin.write(
+            "nvidia-smi",
+            "#!/bin/sh\necho 'No devices were found'\nexit 6\n",
+        );
+        let command = bin.path().join("nvidia-smi");
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut source = NvidiaSmiSource::new(Some(command));
+        assert!(source.read_sensors().unwrap().is_empty());
+        assert!(source.command.is_some());
+
+        let mut source = NvidiaSmiSource::new(Some(bin.path().join("missing")));
+        assert!(source.read_sensors().unwrap().is_empty());
+        assert!(source.command.is_none());
+    }
+
+    #[test]
+    fn serves_the_latest_run_between_refreshes() {
+        let bin = Fixture::new();
+        let runs = bin.path().join("runs");
+        bin.write(
+            "nvidia-smi",
+            &format!(
+                "#!/bin/sh
+echo run >> '{}'
+echo '{}'
+",
+                runs.display(),
+                RTX
+            ),
+        );
+        let command = bin.path().join("nvidia-smi");
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut source = NvidiaSmiSource::new(Some(command));
+        for _ in 0..5 {
+            assert_eq!(source.read_sensors().unwrap().len(), 7);
+        }
+        assert_eq!(fs::read_to_string(&runs).unwrap(), "run\n");
+    }
+}
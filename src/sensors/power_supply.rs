@@ -0,0 +1,251 @@
+use super::{
+    read_attr, read_number, sensor_id, ChannelKind, DeviceKind, SensorLabel, SensorSource,
+};
+use crate::error;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// AC adapters and batteries from `/sys/class/power_supply`: whether mains
+/// power, or a USB-C charger, is online, and each battery's temperature,
+/// power, voltage and charge level.
+pub struct PowerSupplySource {
+    root: PathBuf,
+}
+
+impl PowerSupplySource {
+    pub fn new(sysfs_root: &Path) -> Self {
+        PowerSupplySource {
+            root: sysfs_root.join("class").join("power_supply"),
+        }
+    }
+}
+
+impl SensorSource for PowerSupplySource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Ok(collected_data);
+        };
+        let mut supplies: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect();
+        supplies.sort();
+        // USB-C chargers register as a USB supply that is online while it
+        // powers the machine. Without a battery nothing runs on battery
+        // power, so the ports of a desktop are left out.
+        let has_battery = supplies.iter().any(|supply| {
+            read_attr(&supply.join("type")).as_deref() == Some("Battery")
+                && read_attr(&supply.join("scope")).as_deref() != Some("Device")
+                && read_attr(&supply.join("present")).as_deref() != Some("0")
+        });
+
+        for supply in supplies {
+            let name = supply
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // Peripherals (mice, headsets) report a scope of Device.
+            if read_attr(&supply.join("scope")).as_deref() == Some("Device") {
+                continue;
+            }
+            let mut channel = |channel: &str, label: &str, kind, value, min, max| {
+                collected_data.push(SensorLabel {
+                    id: sensor_id(&["power_supply", &name, channel]),
+                    label: format!("{} {}", name, label),
+                    chip: name.clone(),
+                    kind,
+                    device: DeviceKind::Battery,
+                    value,
+                    min,
+                    max,
+                    ..Default::default()
+                });
+            };
+
+            match read_attr(&supply.join("type")).as_deref() {
+                Some(kind @ ("Mains" | "USB")) if kind == "Mains" || has_battery => {
+                    channel(
+                        "online",
+                        "online",
+                        ChannelKind::State,
+                        read_number(&supply.join("online")),
+                        None,
+                        None,
+                    );
+                }
+                Some("Battery") => {
+                    if read_attr(&supply.join("present")).as_deref() == Some("0") {
+                        continue;
+                    }
+                    // Tenths of a degree.
+                    let temp = supply.join("temp");
+                    if temp.exists() {
+                        let min = read_number(&supply.join("temp_alert_min")).map(|t| t / 10.0);
+                        let max = read_number(&supply.join("temp_alert_max")).map(|t| t / 10.0);
+                        let value = read_number(&temp).map(|t| t / 10.0);
+                        channel("temp", "temp", ChannelKind::Temperature, value, min, max);
+                    }
+                    if let Some(watts) = battery_power(&supply) {
+                        channel("power", "power", ChannelKind::Power, watts, None, None);
+                    }
+                    let voltage = supply.join("voltage_now");
+                    if voltage.exists() {
+                        let value = read_number(&voltage).map(|uv| uv / 1_000_000.0);
+                        let min = read_number(&supply.join("voltage_min_design"))
+                            .map(|uv| uv / 1_000_000.0);
+                        channel("voltage", "voltage", ChannelKind::Voltage, value, min, None);
+                    }
+                    let capacity = supply.join("capacity");
+                    if capacity.exists() {
+                        let value = read_number(&capacity);
+                        channel(
+                            "capacity",
+                            "charge",
+                            ChannelKind::Capacity,
+                            value,
+                            None,
+                            None,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(collected_data)
+    }
+}
+
+/// Power flowing in or out of a battery, in watts: `power_now`, or
+/// `current_now` × `voltage_now` for batteries that report current.
+/// `Some(None)` when the battery has the attributes but the read failed.
+fn battery_power(supply: &Path) -> Option<Option<f64>> {
+    let power = supply.join("power_now");
+    if power.exists() {
+        return Some(read_number(&power).map(|uw| uw.abs() / 1_000_000.0));
+    }
+    let current = supply.join("current_now");
+    let voltage = supply.join("voltage_now");
+    if current.exists() && voltage.exists() {
+        let watts = read_number(&current)
+            .zip(read_number(&voltage))
+            .map(|(ua, uv)| (ua * uv).abs() / 1e12);
+        return Some(watts);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PowerSupplySource;
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, SensorSource},
+    };
+
+    #[test]
+    fn reads_mains_and_batteries() {
+        let sysfs = Fixture::new();
+        sysfs
+            .write("class/power_supply/AC/type", "Mains\n")
+            .write("class/power_supply/AC/online", "0\n")
+            .write("class/power_supply/BAT0/type", "Battery\n")
+            .write("class/power_supply/BAT0/present", "1\n")
+            .write("class/power_supply/BAT0/temp", "312\n")
+            .write("class/power_supply/BAT0/temp_alert_max", "550\n")
+            .write("class/power_supply/BAT0/power_now", "-14250000\n")
+            .write("class/power_supply/BAT0/voltage_now", "11850000\n")
+            .write("class/power_supply/BAT0/voltage_min_design", "11400000\n")
+            .write("class/power_supply/BAT0/capacity", "76\n");
+
+        let sensors = PowerSupplySource::new(sysfs.path()).read_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "power_supply/ac/online",
+                "power_supply/bat0/temp",
+                "power_supply/bat0/power",
+                "power_supply/bat0/voltage",
+                "power_supply/bat0/capacity",
+            ]
+        );
+        assert!(sensors[0].kind == ChannelKind::State);
+        assert_eq!(sensors[0].value, Some(0.0));
+        assert_eq!(sensors[1].label, "BAT0 temp");
+        assert_eq!((sensors[1].value, sensors[1].max), (Some(31.2), Some(55.0)));
+        // Discharging batteries report negative power on some drivers.
+        assert_eq!(sensors[2].value, Some(14.25));
+        assert_eq!(
+            (sensors[3].value, sensors[3].min),
+            (Some(11.85), Some(11.4))
+        );
+        assert_eq!(sensors[4].label, "BAT0 charge");
+        assert_eq!(sensors[4].value, Some(76.0));
+    }
+
+    #[test]
+    fn derives_power_from_current() {
+        let sysfs = Fixture::new();
+        sysfs
+            .write("class/power_supply/BAT1/type", "Battery\n")
+            .write("class/power_supply/BAT1/current_now", "1500000\n")
+            .write("class/power_supply/BAT1/voltage_now", "12000000\n");
+
+        let sensors = PowerSupplySource::new(sysfs.path()).read_sensors().unwrap();
+        assert_eq!(sensors.len(), 2);
+        assert!(sensors[0].kind == ChannelKind::Power);
+        assert_eq!(sensors[0].value, Some(18.0));
+
+        // A failed read keeps the channel with a missing sample.
+        sysfs.write("class/power_supply/BAT1/current_now", "");
+        let sensors = PowerSupplySource::new(sysfs.path()).read_sensors().unwrap();
+        assert_eq!(sensors[0].value, None);
+    }
+
+    #[test]
+    fn skips_peripherals_and_empty_bays() {
+        let sysfs = Fixture::new();
+        sysfs
+            .write("class/power_supply/BAT1/type", "Battery\n")
+            .write("class/power_supply/BAT1/present", "0\n")
+            .write("class/power_supply/BAT1/capacity", "0\n")
+            .write("class/power_supply/hidpp_battery_0/type", "Battery\n")
+            .write("class/power_supply/hidpp_battery_0/scope", "Device\n")
+            .write("class/power_supply/hidpp_battery_0/capacity", "60\n")
+            .write("class/power_supply/ucsi-source-psy-0/type", "USB\n")
+            .write("class/power_supply/ucsi-source-psy-0/online", "1\n");
+
+        // Without a battery, USB-C ports do not say AC or battery either.
+        let sensors = PowerSupplySource::new(sysfs.path()).read_sensors().unwrap();
+        assert!(sensors.is_empty());
+    }
+
+    #[test]
+    fn usb_c_chargers_are_ac_adapters() {
+        let sysfs = Fixture::new();
+        sysfs
+            .write("class/power_supply/BAT0/type", "Battery\n")
+            .write("class/power_supply/BAT0/capacity", "40\n")
+            .write("class/power_supply/ucsi-source-psy-0/type", "USB\n")
+            .write("class/power_supply/ucsi-source-psy-0/online", "1\n")
+            .write("class/power_supply/ucsi-source-psy-1/type", "USB\n")
+            .write("class/power_supply/ucsi-source-psy-1/online", "0\n");
+
+        let sensors = PowerSupplySource::new(sysfs.path()).read_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "power_supply/bat0/capacity",
+                "power_supply/ucsi-source-psy-0/online",
+                "power_supply/ucsi-source-psy-1/online",
+            ]
+        );
+        assert!(sensors[1].kind == ChannelKind::State);
+        assert_eq!((sensors[1].value, sensors[2].value), (Some(1.0), Some(0.0)));
+    }
+}
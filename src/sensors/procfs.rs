@@ -0,0 +1,145 @@
+use super::{sensor_id, ChannelKind, DeviceKind, SensorLabel, SensorSource};
+use crate::error;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// Per-core and total CPU utilization over the interval since the previous
+/// read of `/proc/stat`, the `/proc/loadavg` averages, and the share of that
+/// interval tasks spent stalled on CPU from `/proc/pressure/cpu`.
+pub struct ProcStatSource {
+    root: PathBuf,
+    previous: HashMap<String, (u64, u64)>,
+    /// PSI `total=` stall time in microseconds, by line, and when it was read.
+    pressure: HashMap<String, (u64, Instant)>,
+}
+
+impl ProcStatSource {
+    pub fn new(procfs_root: &Path) -> Self {
+        ProcStatSource {
+            root: procfs_root.to_path_buf(),
+            previous: HashMap::new(),
+            pressure: HashMap::new(),
+        }
+    }
+}
+
+impl SensorSource for ProcStatSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        let Ok(stat) = fs::read_to_string(self.root.join("stat")) else {
+            return Ok(collected_data);
+        };
+
+        let mut cpu_count = 0;
+        for line in stat.lines().filter(|line| line.starts_with("cpu")) {
+            let mut fields = line.split_whitespace();
+            let name = fields.next().unwrap_or_default().to_string();
+            // user nice system idle iowait irq softirq steal; guest time is
+            // already counted in user.
+            let ticks: Vec<u64> = fields.take(8).filter_map(|f| f.parse().ok()).collect();
+            if ticks.len() < 4 {
+                continue;
+            }
+            if name != "cpu" {
+                cpu_count += 1;
+            }
+
+            let total: u64 = ticks.iter().sum();
+            let idle = ticks[3] + ticks.get(4).copied().unwrap_or(0);
+            let busy = total - idle;
+            // The first read only starts the interval: a missing sample.
+            let previous = self.previous.insert(name.clone(), (busy, total));
+            let utilization = previous.and_then(|(last_busy, last_total)| {
+                let elapsed = total.saturating_sub(last_total);
+                (elapsed > 0)
+                    .then(|| busy.saturating_sub(last_busy) as f64 * 100.0 / elapsed as f64)
+            });
+
+            let label = if name == "cpu" {
+                "total".to_string()
+            } else {
+                name
+            };
+            collected_data.push(channel(
+                "stat",
+                label,
+                ChannelKind::Utilization,
+                utilization,
+                None,
+            ));
+        }
+
+        if let Ok(loadavg) = fs::read_to_string(self.root.join("loadavg")) {
+            let averages = loadavg.split_whitespace().take(3);
+            for (window, value) in ["1m", "5m", "15m"].into_iter().zip(averages) {
+                if let Ok(value) = value.parse() {
+                    let max = (cpu_count > 0).then_some(cpu_count as f64);
+                    collected_data.push(channel(
+                        "loadavg",
+                        window.to_string(),
+                        ChannelKind::Load,
+                        Some(value),
+                        max,
+                    ));
+                }
+            }
+        }
+
+        // Kernels without CONFIG_PSI, or booted with psi=0, have no file.
+        if let Ok(pressure) = fs::read_to_string(self.root.join("pressure").join("cpu")) {
+            let now = Instant::now();
+            for line in pressure.lines() {
+                let mut fields = line.split_whitespace();
+                let Some(line_name) = fields.next() else {
+                    continue;
+                };
+                let Some(total) = fields
+                    .find_map(|field| field.strip_prefix("total="))
+                    .and_then(|total| total.parse::<u64>().ok())
+                else {
+                    continue;
+                };
+                let previous = self.pressure.insert(line_name.to_string(), (total, now));
+                let stalled = previous.and_then(|(last_total, last_read)| {
+                    let elapsed = now.duration_since(last_read).as_micros();
+                    (elapsed > 0).then(|| {
+                        (total.saturating_sub(last_total) as f64 * 100.0 / elapsed as f64)
+                            .min(100.0)
+                    })
+                });
+                collected_data.push(channel(
+                    "pressure",
+                    format!("cpu {}", line_name),
+                    ChannelKind::Pressure,
+                    stalled,
+                    None,
+                ));
+            }
+        }
+
+        Ok(collected_data)
+    }
+}
+
+fn channel(
+    file: &str,
+    label: String,
+    kind: ChannelKind,
+    value: Option<f64>,
+    max: Option<f64>,
+) -> SensorLabel {
+    SensorLabel {
+        id: sensor_id(&["procfs", file, &label]),
+        label,
+        chip: "procfs".to_string(),
+        kind,
+        device: DeviceKind::Cpu,
+        value,
+        max,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,166 @@
+use super::{sensor_id, ChannelKind, DeviceKind, SensorLabel, SensorSource};
+use crate::error;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// Package/core/dram energy counters from `/sys/class/powercap/intel-rapl*`,
+/// reported as the average watts since the previous read.
+pub struct RaplSource {
+    root: PathBuf,
+    previous: HashMap<PathBuf, (u64, Instant)>,
+}
+
+impl RaplSource {
+    pub fn new(sysfs_root: &Path) -> Self {
+        RaplSource {
+            root: sysfs_root.join("class").join("powercap"),
+            previous: HashMap::new(),
+        }
+    }
+}
+
+impl SensorSource for RaplSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Ok(collected_data);
+        };
+
+        let mut zones: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("intel-rapl:"))
+            })
+            .collect();
+        zones.sort();
+
+        for zone in zones {
+            // energy_uj is root-only on most kernels; skip zones we cannot read.
+            let Some(energy) = read_u64(&zone.join("energy_uj")) else {
+                continue;
+            };
+            let now = Instant::now();
+            let previous = self.previous.insert(zone.clone(), (energy, now));
+            // The first read only starts the interval: the zone is listed
+            // with a missing sample.
+            let watts = previous.and_then(|(last_energy, last_time)| {
+                let seconds = now.duration_since(last_time).as_secs_f64();
+                if seconds <= 0.0 {
+                    return None;
+                }
+                let delta = energy_delta(last_energy, energy, || {
+                    read_u64(&zone.join("max_energy_range_uj"))
+                })?;
+                Some(delta as f64 / 1_000_000.0 / seconds)
+            });
+
+            let zone_id = zone.file_name().unwrap_or_default().to_string_lossy();
+            collected_data.push(SensorLabel {
+                id: sensor_id(&["intel-rapl", &zone_id, "energy"]),
+                label: zone_label(&zone),
+                chip: "intel-rapl".to_string(),
+                kind: ChannelKind::Power,
+                device: DeviceKind::Cpu,
+                value: watts,
+                ..Default::default()
+            });
+        }
+
+        Ok(collected_data)
+    }
+}
+
+/// Microjoules used between two counter reads. `range` is only read when
+/// the counter wrapped at `max_energy_range_uj` since the last read.
+fn energy_delta(last: u64, energy: u64, range: impl FnOnce() -> Option<u64>) -> Option<u64> {
+    if energy >= last {
+        return Some(energy - last);
+    }
+    Some(range()?.saturating_sub(last) + energy)
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// "package-0" for top-level zones, "package-0/core" for subzones.
+fn zone_label(zone: &Path) -> String {
+    let name = |path: &Path| {
+        fs::read_to_string(path.join("name"))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            })
+    };
+
+    let zone_id = zone
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    match zone_id.rsplit_once(':') {
+        Some((parent_id, _)) if parent_id != "intel-rapl" => {
+            format!("{}/{}", name(&zone.with_file_name(parent_id)), name(zone))
+        }
+        _ => name(zone),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{energy_delta, RaplSource};
+    use crate::{fixture::Fixture, sensors::SensorSource};
+    use std::time::Duration;
+
+    const RANGE: u64 = 262_143_328_850;
+
+    #[test]
+    fn counter_deltas() {
+        assert_eq!(energy_delta(1_000, 4_500, || None), Some(3_500));
+        assert_eq!(
+            energy_delta(RANGE - 1_000, 500, || Some(RANGE)),
+            Some(1_500)
+        );
+        // Without the range a wrapped counter gives no sample.
+        assert_eq!(energy_delta(RANGE - 1_000, 500, || None), None);
+    }
+
+    #[test]
+    fn reads_average_watts_across_a_wrap() {
+        let sysfs = Fixture::new();
+        let zone = "class/powercap/intel-rapl:0";
+        sysfs
+            .write(&format!("{}/name", zone), "package-0\n")
+            .write(
+                &format!("{}/energy_uj", zone),
+                &format!("{}\n", RANGE - 4_000_000),
+            )
+            .write(
+                &format!("{}/max_energy_range_uj", zone),
+                &format!("{}\n", RANGE),
+            );
+        let mut source = RaplSource::new(sysfs.path());
+
+        let sensors = source.read_sensors().unwrap();
+        assert_eq!(sensors[0].id, "intel-rapl/intel-rapl:0/energy");
+        assert_eq!(sensors[0].label, "package-0");
+        assert_eq!(sensors[0].value, None);
+
+        // 4 J before the wrap and 16 J after it, over two seconds.
+        for (_, time) in source.previous.values_mut() {
+            *time -= Duration::from_secs(2);
+        }
+        sysfs.write(&format!("{}/energy_uj", zone), "16000000\n");
+        let watts = source.read_sensors().unwrap()[0].value.unwrap();
+        assert!((watts - 10.0).abs() < 0.1, "{} W", watts);
+    }
+}
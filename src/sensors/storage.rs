@@ -0,0 +1,76 @@
+use super::{read_attr, DeviceIdentity};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Identity of the drive behind a storage hwmon chip: an NVMe controller
+/// (`nvme` driver, `/sys/class/nvme/nvmeX`) or a SCSI disk (`drivetemp`,
+/// `/sys/block/sdX/device`). `None` when sysfs names no drive.
+pub fn drive_identity(hwmon_path: &Path) -> Option<DeviceIdentity> {
+    let device = fs::canonicalize(hwmon_path.join("device")).ok()?;
+
+    let identity = if let Some(controller) = nvme_controller(&device) {
+        let controller_name = controller
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        DeviceIdentity {
+            // The namespace (nvme0n1) is what lsblk and mount show.
+            name: first_entry(&controller, |name| {
+                name.strip_prefix(controller_name.as_str())
+                    .is_some_and(|rest| rest.starts_with('n'))
+            })
+            .or(Some(controller_name)),
+            model: read_attr(&controller.join("model")),
+            serial: read_attr(&controller.join("serial")),
+            firmware: read_attr(&controller.join("firmware_rev")),
+        }
+    } else if device.join("block").is_dir() {
+        let vendor = read_attr(&device.join("vendor")).filter(|vendor| vendor != "ATA");
+        let model = read_attr(&device.join("model"));
+        DeviceIdentity {
+            name: first_entry(&device.join("block"), |_| true),
+            model: match (vendor, model) {
+                (Some(vendor), Some(model)) => Some(format!("{} {}", vendor, model)),
+                (vendor, model) => model.or(vendor),
+            },
+            serial: scsi_serial(&device),
+            firmware: read_attr(&device.join("rev")),
+        }
+    } else {
+        return None;
+    };
+
+    (identity.name.is_some() || identity.model.is_some()).then_some(identity)
+}
+
+/// The controller directory: the hwmon parent itself on current kernels,
+/// or `nvme/nvmeX` under the PCI function when hwmon hangs off that.
+fn nvme_controller(device: &Path) -> Option<PathBuf> {
+    if device.join("firmware_rev").exists() {
+        return Some(device.to_path_buf());
+    }
+    let nvme = device.join("nvme");
+    first_entry(&nvme, |name| name.starts_with("nvme")).map(|name| nvme.join(name))
+}
+
+/// Unit serial number from VPD page 0x80: a 4-byte header, then ASCII.
+fn scsi_serial(device: &Path) -> Option<String> {
+    let page = fs::read(device.join("vpd_pg80")).ok()?;
+    let serial = String::from_utf8_lossy(page.get(4..)?)
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+        .to_string();
+    (!serial.is_empty()).then_some(serial)
+}
+
+fn first_entry(dir: &Path, wanted: impl Fn(&str) -> bool) -> Option<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| wanted(name))
+        .collect();
+    names.sort();
+    names.into_iter().next()
+}
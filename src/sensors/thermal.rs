@@ -0,0 +1,183 @@
+use super::{sensor_id, ChannelKind, Classifier, SensorLabel, SensorSource, TripPoint};
+use crate::error;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Zone temperatures from `/sys/class/thermal/thermal_zone*`, for boards and
+/// VMs where hwmon has no CPU driver.
+pub struct ThermalZoneSource {
+    root: PathBuf,
+    classifier: Classifier,
+}
+
+impl ThermalZoneSource {
+    pub fn new(sysfs_root: &Path, classifier: Classifier) -> Self {
+        ThermalZoneSource {
+            root: sysfs_root.join("class").join("thermal"),
+            classifier,
+        }
+    }
+}
+
+impl SensorSource for ThermalZoneSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+
+        for zone in list_prefixed(&self.root, "thermal_zone") {
+            // Disabled zones fail the read with ENODATA; leave them out.
+            let Some(millidegrees) = read_i64(&zone.join("temp")) else {
+                continue;
+            };
+            let zone_type = read_type(&zone);
+
+            let zone_name = zone.file_name().unwrap_or_default().to_string_lossy();
+            collected_data.push(SensorLabel {
+                id: sensor_id(&[&zone_type, &zone_name, "temp"]),
+                label: zone_type.clone(),
+                device: self.classifier.classify(&zone_type),
+                chip: zone_type,
+                kind: ChannelKind::Temperature,
+                value: Some(millidegrees as f64 / 1000.0),
+                ..Default::default()
+            });
+        }
+
+        Ok(collected_data)
+    }
+
+    fn trip_points(&self) -> Vec<TripPoint> {
+        let mut trips = Vec::new();
+        for zone in list_prefixed(&self.root, "thermal_zone") {
+            let zone_type = read_type(&zone);
+            let mut index = 0;
+            while let Some(millidegrees) =
+                read_i64(&zone.join(format!("trip_point_{}_temp", index)))
+            {
+                let kind = fs::read_to_string(zone.join(format!("trip_point_{}_type", index)))
+                    .map(|kind| kind.trim().to_string())
+                    .unwrap_or_else(|_| "unknown".to_string());
+                // Unused trip slots report a zero or negative temperature.
+                if millidegrees > 0 {
+                    trips.push(TripPoint {
+                        zone: zone_type.clone(),
+                        kind,
+                        temp: millidegrees as f64 / 1000.0,
+                    });
+                }
+                index += 1;
+            }
+        }
+        trips
+    }
+}
+
+/// Kernel cooling devices (fans, cpufreq/powerclamp throttles) as
+/// `cur_state` out of `max_state`, so passive throttling shows up in sessions.
+pub struct CoolingDeviceSource {
+    root: PathBuf,
+    classifier: Classifier,
+}
+
+impl CoolingDeviceSource {
+    pub fn new(sysfs_root: &Path, classifier: Classifier) -> Self {
+        CoolingDeviceSource {
+            root: sysfs_root.join("class").join("thermal"),
+            classifier,
+        }
+    }
+}
+
+impl SensorSource for CoolingDeviceSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+
+        for device in list_prefixed(&self.root, "cooling_device") {
+            let Some(state) = read_i64(&device.join("cur_state")) else {
+                continue;
+            };
+            let device_type = read_type(&device);
+            let index = device
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .trim_start_matches("cooling_device")
+                .to_string();
+
+            collected_data.push(SensorLabel {
+                id: sensor_id(&[&device_type, &format!("cooling_device{}", index), "state"]),
+                label: format!("{} {}", device_type, index),
+                device: self.classifier.classify(&device_type),
+                chip: device_type,
+                kind: ChannelKind::Cooling,
+                value: Some(state as f64),
+                // Some drivers register a device with no states at all.
+                max: read_i64(&device.join("max_state"))
+                    .filter(|&max| max > 0)
+                    .map(|max| max as f64),
+                ..Default::default()
+            });
+        }
+
+        Ok(collected_data)
+    }
+}
+
+/// Entries of `dir` starting with `prefix`, in numeric order.
+fn list_prefixed(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<(u32, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let index = name.strip_prefix(prefix)?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    paths.sort_by_key(|(index, _)| *index);
+    paths.into_iter().map(|(_, path)| path).collect()
+}
+
+fn read_type(dir: &Path) -> String {
+    fs::read_to_string(dir.join("type"))
+        .map(|kind| kind.trim().to_string())
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+fn read_i64(path: &Path) -> Option<i64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoolingDeviceSource;
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, Classifier, SensorSource},
+    };
+
+    #[test]
+    fn reads_cooling_devices() {
+        let sysfs = Fixture::new();
+        sysfs
+            .write("class/thermal/cooling_device0/type", "Processor\n")
+            .write("class/thermal/cooling_device0/cur_state", "2\n")
+            .write("class/thermal/cooling_device0/max_state", "10\n")
+            .write("class/thermal/cooling_device1/type", "intel_powerclamp\n")
+            .write("class/thermal/cooling_device1/cur_state", "0\n")
+            .write("class/thermal/cooling_device1/max_state", "0\n");
+
+        let sensors = CoolingDeviceSource::new(sysfs.path(), Classifier::new(Vec::new()))
+            .read_sensors()
+            .unwrap();
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].id, "processor/cooling_device0/state");
+        assert!(sensors[0].kind == ChannelKind::Cooling);
+        assert_eq!((sensors[0].value, sensors[0].max), (Some(2.0), Some(10.0)));
+        // max_state 0 is no scale at all.
+        assert_eq!((sensors[1].value, sensors[1].max), (Some(0.0), None));
+    }
+}
@@ -0,0 +1,106 @@
+use super::{sensor_id, ChannelKind, DeviceKind, SensorLabel, SensorSource};
+use crate::error;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Thermal throttling events per CPU package since the previous read, from
+/// Intel's `cpu*/thermal_throttle/{core,package}_throttle_count`. Core
+/// counters are shared by hyperthread siblings and package counters by
+/// every CPU in the package, so each is counted once.
+pub struct ThrottleSource {
+    root: PathBuf,
+    previous: HashMap<String, u64>,
+}
+
+impl ThrottleSource {
+    pub fn new(sysfs_root: &Path) -> Self {
+        ThrottleSource {
+            root: sysfs_root.join("devices").join("system").join("cpu"),
+            previous: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PackageCounts {
+    /// `core_throttle_count` by core ID.
+    cores: BTreeMap<u32, u64>,
+    package: Option<u64>,
+}
+
+impl SensorSource for ThrottleSource {
+    fn read_sensors(&mut self) -> error::Result<Vec<SensorLabel>> {
+        let mut collected_data = Vec::new();
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Ok(collected_data);
+        };
+
+        let mut packages: BTreeMap<u32, PackageCounts> = BTreeMap::new();
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(index) = name
+                .strip_prefix("cpu")
+                .and_then(|index| index.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            // Only Intel's therm_throt driver creates this directory.
+            let throttle = entry.path().join("thermal_throttle");
+            if !throttle.is_dir() {
+                continue;
+            }
+            let topology = entry.path().join("topology");
+            let package = read_count(&topology.join("physical_package_id")).unwrap_or(0) as u32;
+            let core = read_count(&topology.join("core_id")).map_or(index, |core| core as u32);
+
+            let counts = packages.entry(package).or_default();
+            if let Some(count) = read_count(&throttle.join("core_throttle_count")) {
+                let shared = counts.cores.entry(core).or_default();
+                *shared = (*shared).max(count);
+            }
+            if let Some(count) = read_count(&throttle.join("package_throttle_count")) {
+                counts.package = Some(counts.package.unwrap_or_default().max(count));
+            }
+        }
+
+        for (package, counts) in packages {
+            let package_name = format!("package{}", package);
+            let totals = [
+                (
+                    "core",
+                    (!counts.cores.is_empty()).then(|| counts.cores.values().sum()),
+                ),
+                ("package", counts.package),
+            ];
+            for (counter, total) in totals {
+                let Some(total) = total else {
+                    continue;
+                };
+                let id = sensor_id(&["thermal_throttle", &package_name, counter]);
+                // The first read only starts the interval: a missing sample.
+                let increment = self
+                    .previous
+                    .insert(id.clone(), total)
+                    .map(|last| total.saturating_sub(last) as f64);
+                collected_data.push(SensorLabel {
+                    id,
+                    label: format!("{} {} throttle", package_name, counter),
+                    chip: "thermal_throttle".to_string(),
+                    kind: ChannelKind::Throttle,
+                    device: DeviceKind::Cpu,
+                    value: increment,
+                    ..Default::default()
+                });
+            }
+        }
+
+        Ok(collected_data)
+    }
+}
+
+fn read_count(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
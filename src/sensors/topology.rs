@@ -0,0 +1,421 @@
+use super::{ChannelKind, SensorLabel};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where a per-core reading sits in the CPU: its package, the CCD or core
+/// it measures, and the logical CPUs behind it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub package: u32,
+    pub ccd: Option<u32>,
+    pub core: Option<u32>,
+    pub cpus: Vec<u32>,
+}
+
+impl Placement {
+    /// Orders readings package first, then CCD, then core.
+    pub fn sort_key(&self) -> (u32, Option<u32>, Option<u32>) {
+        (self.package, self.ccd, self.core)
+    }
+
+    /// Whether this reading covers less than a whole package.
+    pub fn is_local(&self) -> bool {
+        self.ccd.is_some() || self.core.is_some()
+    }
+
+    /// Short form for the live view, e.g. `pkg 0 core 3 · cpu 3,7`.
+    pub fn describe(&self) -> String {
+        let mut text = format!("pkg {}", self.package);
+        if let Some(ccd) = self.ccd {
+            text.push_str(&format!(" ccd {}", ccd));
+        }
+        if let Some(core) = self.core {
+            text.push_str(&format!(" core {}", core));
+        }
+        if !self.cpus.is_empty() {
+            text.push_str(&format!(" · cpu {}", cpu_list(&self.cpus, ",")));
+        }
+        text
+    }
+
+    /// `key=value` fields for the `# Topology:` session header line.
+    pub fn header_fields(&self) -> String {
+        let mut fields = vec![format!("package={}", self.package)];
+        if let Some(ccd) = self.ccd {
+            fields.push(format!("ccd={}", ccd));
+        }
+        if let Some(core) = self.core {
+            fields.push(format!("core={}", core));
+        }
+        // Ranges are space separated so the header stays comma separated.
+        fields.push(format!("cpus={}", cpu_list(&self.cpus, " ")));
+        fields.join(",")
+    }
+}
+
+/// Formats CPU numbers the way the kernel prints cpu lists: `0-3,8-11`.
+fn cpu_list(cpus: &[u32], separator: &str) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+struct LogicalCpu {
+    id: u32,
+    package: u32,
+    core: u32,
+    /// L3 cache id, shared by the cores of one CCX.
+    l3: Option<u32>,
+}
+
+/// Logical CPUs from `devices/system/cpu/cpu*/topology`, read once at start.
+#[derive(Default)]
+pub struct CpuTopology {
+    cpus: Vec<LogicalCpu>,
+    /// Tccd channels per package, counted from the k10temp/zenpower chips
+    /// themselves: lm-sensors `ignore` may have dropped some from the frame.
+    ccd_counts: HashMap<u32, u32>,
+}
+
+impl CpuTopology {
+    pub fn new(sysfs_root: &Path) -> Self {
+        let root = sysfs_root.join("devices").join("system").join("cpu");
+        let Ok(entries) = fs::read_dir(&root) else {
+            return CpuTopology::default();
+        };
+
+        let mut cpus: Vec<LogicalCpu> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let id = name.strip_prefix("cpu")?.parse().ok()?;
+                let path: PathBuf = entry.path();
+                let topology = path.join("topology");
+                // Offline CPUs have no topology directory.
+                Some(LogicalCpu {
+                    id,
+                    package: read_u32(&topology.join("physical_package_id"))?,
+                    core: read_u32(&topology.join("core_id"))?,
+                    l3: read_u32(&path.join("cache").join("index3").join("id")),
+                })
+            })
+            .collect();
+        cpus.sort_by_key(|cpu| cpu.id);
+        CpuTopology {
+            cpus,
+            ccd_counts: count_ccds(&sysfs_root.join("class").join("hwmon")),
+        }
+    }
+
+    /// Fills in `placement` for the channels that map onto the topology:
+    /// coretemp package and core readings, k10temp/zenpower Tctl and Tccd
+    /// readings, and the per-CPU frequency and utilization channels.
+    pub fn place(&self, sensors: &mut [SensorLabel]) {
+        if self.cpus.is_empty() {
+            return;
+        }
+
+        // The number of CCDs per package tells how many L3 domains make up
+        // one CCD (two CCX per CCD on Zen 2, one from Zen 3 on). A TccdN
+        // reading also means there are at least N.
+        let mut ccd_counts = self.ccd_counts.clone();
+        for sensor in sensors.iter() {
+            if let Some((package, ccd)) = amd_ccd(sensor) {
+                let count = ccd_counts.entry(package).or_default();
+                *count = (*count).max(ccd + 1);
+            }
+        }
+
+        for sensor in sensors.iter_mut() {
+            sensor.placement = match (sensor.kind, sensor.chip.as_str()) {
+                (ChannelKind::Temperature, "coretemp") => self.place_coretemp(sensor),
+                (ChannelKind::Temperature, "k10temp" | "zenpower") => {
+                    let package = amd_node(sensor);
+                    match amd_ccd(sensor) {
+                        Some((package, ccd)) => self.ccd(package, ccd, &ccd_counts),
+                        None if ["Tctl", "Tdie"].contains(&sensor.label.as_str()) => {
+                            self.package(package)
+                        }
+                        None => None,
+                    }
+                }
+                (ChannelKind::Frequency | ChannelKind::Utilization, "cpufreq" | "procfs") => sensor
+                    .label
+                    .strip_prefix("cpu")
+                    .and_then(|id| id.parse().ok())
+                    .and_then(|id| self.cpu(id, &ccd_counts)),
+                _ => None,
+            };
+        }
+    }
+
+    fn place_coretemp(&self, sensor: &SensorLabel) -> Option<Placement> {
+        if let Some(package) = sensor.label.strip_prefix("Package id ") {
+            return self.package(package.trim().parse().ok()?);
+        }
+        let core = sensor.label.strip_prefix("Core ")?.trim().parse().ok()?;
+        // coretemp registers one platform device per package: coretemp.N.
+        let package = sensor
+            .id
+            .split('/')
+            .nth(1)
+            .and_then(|address| address.strip_prefix("coretemp."))
+            .and_then(|index| index.parse().ok())
+            .unwrap_or(0);
+        self.core(package, core)
+    }
+
+    fn package(&self, package: u32) -> Option<Placement> {
+        let cpus = self.cpu_ids(|cpu| cpu.package == package);
+        (!cpus.is_empty()).then_some(Placement {
+            package,
+            ccd: None,
+            core: None,
+            cpus,
+        })
+    }
+
+    fn core(&self, package: u32, core: u32) -> Option<Placement> {
+        let cpus = self.cpu_ids(|cpu| cpu.package == package && cpu.core == core);
+        (!cpus.is_empty()).then_some(Placement {
+            package,
+            ccd: None,
+            core: Some(core),
+            cpus,
+        })
+    }
+
+    fn ccd(&self, package: u32, ccd: u32, ccd_counts: &HashMap<u32, u32>) -> Option<Placement> {
+        let cpus =
+            self.cpu_ids(|cpu| cpu.package == package && self.ccd_of(cpu, ccd_counts) == Some(ccd));
+        (!cpus.is_empty()).then_some(Placement {
+            package,
+            ccd: Some(ccd),
+            core: None,
+            cpus,
+        })
+    }
+
+    fn cpu(&self, id: u32, ccd_counts: &HashMap<u32, u32>) -> Option<Placement> {
+        let cpu = self.cpus.iter().find(|cpu| cpu.id == id)?;
+        Some(Placement {
+            package: cpu.package,
+            ccd: self.ccd_of(cpu, ccd_counts),
+            core: Some(cpu.core),
+            cpus: self.cpu_ids(|other| other.package == cpu.package && other.core == cpu.core),
+        })
+    }
+
+    /// CCD index of a logical CPU within its package, known only when the
+    /// package reports per-CCD temperatures.
+    fn ccd_of(&self, cpu: &LogicalCpu, ccd_counts: &HashMap<u32, u32>) -> Option<u32> {
+        let ccds = *ccd_counts.get(&cpu.package)?;
+        let mut domains: Vec<u32> = self
+            .cpus
+            .iter()
+            .filter(|other| other.package == cpu.package)
+            .filter_map(|other| other.l3)
+            .collect();
+        domains.sort_unstable();
+        domains.dedup();
+        let rank = domains.iter().position(|&l3| Some(l3) == cpu.l3)? as u32;
+        let per_ccd = (domains.len() as u32 / ccds).max(1);
+        Some(rank / per_ccd)
+    }
+
+    fn cpu_ids(&self, filter: impl Fn(&LogicalCpu) -> bool) -> Vec<u32> {
+        self.cpus
+            .iter()
+            .filter(|cpu| filter(cpu))
+            .map(|cpu| cpu.id)
+            .collect()
+    }
+}
+
+/// Node of an AMD data fabric function: device 18h is node 0, 19h node 1.
+fn amd_node(sensor: &SensorLabel) -> u32 {
+    node_of_address(sensor.id.split('/').nth(1).unwrap_or_default())
+}
+
+fn node_of_address(address: &str) -> u32 {
+    address
+        .get(8..10)
+        .and_then(|device| u32::from_str_radix(device, 16).ok())
+        .and_then(|device| device.checked_sub(0x18))
+        .unwrap_or(0)
+}
+
+/// `TccdN` labels per node across the k10temp and zenpower chips in
+/// `hwmon_dir`.
+fn count_ccds(hwmon_dir: &Path) -> HashMap<u32, u32> {
+    let mut counts = HashMap::new();
+    let Ok(entries) = fs::read_dir(hwmon_dir) else {
+        return counts;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = fs::read_to_string(path.join("name")).unwrap_or_default();
+        if !["k10temp", "zenpower"].contains(&name.trim()) {
+            continue;
+        }
+        let Ok(files) = fs::read_dir(&path) else {
+            continue;
+        };
+        let ccds = files
+            .filter_map(Result::ok)
+            .filter(|file| file.file_name().to_string_lossy().ends_with("_label"))
+            .filter(|file| {
+                fs::read_to_string(file.path()).is_ok_and(|label| label.starts_with("Tccd"))
+            })
+            .count() as u32;
+        let address = fs::read_link(path.join("device"))
+            .ok()
+            .and_then(|device| Some(device.file_name()?.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        *counts.entry(node_of_address(&address)).or_default() += ccds;
+    }
+    counts
+}
+
+/// Package and zero-based CCD of a `TccdN` reading.
+fn amd_ccd(sensor: &SensorLabel) -> Option<(u32, u32)> {
+    if sensor.kind != ChannelKind::Temperature
+        || !["k10temp", "zenpower"].contains(&sensor.chip.as_str())
+    {
+        return None;
+    }
+    let index: u32 = sensor.label.strip_prefix("Tccd")?.parse().ok()?;
+    Some((amd_node(sensor), index.checked_sub(1)?))
+}
+
+fn read_u32(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CpuTopology;
+    use crate::{
+        fixture::Fixture,
+        sensors::{ChannelKind, SensorLabel},
+    };
+
+    /// One package of four single-thread cores, two per L3 domain, and a
+    /// k10temp chip with `tccds` Tccd channels.
+    fn ryzen(tccds: u32) -> Fixture {
+        let sysfs = Fixture::new();
+        for cpu in 0..4 {
+            let path = format!("devices/system/cpu/cpu{}", cpu);
+            sysfs
+                .write(&format!("{}/topology/physical_package_id", path), "0\n")
+                .write(&format!("{}/topology/core_id", path), &format!("{}\n", cpu))
+                .write(
+                    &format!("{}/cache/index3/id", path),
+                    &format!("{}\n", cpu / 2),
+                );
+        }
+        sysfs
+            .write("class/hwmon/hwmon0/name", "k10temp\n")
+            .write("class/hwmon/hwmon0/temp1_label", "Tctl\n")
+            .link(
+                "class/hwmon/hwmon0/device",
+                "devices/pci0000:00/0000:00:18.3",
+            );
+        for ccd in 1..=tccds {
+            sysfs.write(
+                &format!("class/hwmon/hwmon0/temp{}_label", ccd + 2),
+                &format!("Tccd{}\n", ccd),
+            );
+        }
+        sysfs
+    }
+
+    fn reading(chip: &str, label: &str, kind: ChannelKind) -> SensorLabel {
+        SensorLabel {
+            id: format!("{}/0000:00:18.3/{}", chip, label.to_lowercase()),
+            label: label.to_string(),
+            chip: chip.to_string(),
+            kind,
+            ..Default::default()
+        }
+    }
+
+    fn cpus(sensor: &SensorLabel) -> Option<(Option<u32>, Vec<u32>)> {
+        let placement = sensor.placement.as_ref()?;
+        Some((placement.ccd, placement.cpus.clone()))
+    }
+
+    #[test]
+    fn one_ccd_per_l3_domain() {
+        let sysfs = ryzen(2);
+        let mut sensors = [
+            reading("k10temp", "Tctl", ChannelKind::Temperature),
+            reading("k10temp", "Tccd1", ChannelKind::Temperature),
+            reading("k10temp", "Tccd2", ChannelKind::Temperature),
+            reading("cpufreq", "cpu3", ChannelKind::Frequency),
+        ];
+        CpuTopology::new(sysfs.path()).place(&mut sensors);
+
+        assert_eq!(cpus(&sensors[0]), Some((None, vec![0, 1, 2, 3])));
+        assert_eq!(cpus(&sensors[1]), Some((Some(0), vec![0, 1])));
+        assert_eq!(cpus(&sensors[2]), Some((Some(1), vec![2, 3])));
+        assert_eq!(cpus(&sensors[3]), Some((Some(1), vec![3])));
+    }
+
+    #[test]
+    fn two_l3_domains_per_ccd() {
+        // Zen 2: one CCD made of two CCX.
+        let sysfs = ryzen(1);
+        let mut sensors = [
+            reading("k10temp", "Tccd1", ChannelKind::Temperature),
+            reading("cpufreq", "cpu3", ChannelKind::Frequency),
+        ];
+        CpuTopology::new(sysfs.path()).place(&mut sensors);
+
+        assert_eq!(cpus(&sensors[0]), Some((Some(0), vec![0, 1, 2, 3])));
+        assert_eq!(cpus(&sensors[1]), Some((Some(0), vec![3])));
+    }
+
+    #[test]
+    fn ignored_tccd_channels_keep_their_ccd() {
+        // lm-sensors ignored Tccd1, or Tccd2; the chip still has both.
+        let sysfs = ryzen(2);
+        let topology = CpuTopology::new(sysfs.path());
+        for (label, ccd, expected) in [("Tccd2", 1, [2, 3]), ("Tccd1", 0, [0, 1])] {
+            let mut sensors = [reading("k10temp", label, ChannelKind::Temperature)];
+            topology.place(&mut sensors);
+            assert_eq!(cpus(&sensors[0]), Some((Some(ccd), expected.to_vec())));
+        }
+    }
+
+    #[test]
+    fn counts_tccd_readings_without_the_chip() {
+        // Readings from a source the sysfs scan does not know about.
+        let sysfs = ryzen(0);
+        let mut sensors = [
+            reading("zenpower", "Tccd1", ChannelKind::Temperature),
+            reading("zenpower", "Tccd2", ChannelKind::Temperature),
+        ];
+        CpuTopology::new(sysfs.path()).place(&mut sensors);
+
+        assert_eq!(cpus(&sensors[1]), Some((Some(1), vec![2, 3])));
+    }
+}
@@ -1,6 +1,10 @@
 use crate::{
+    error::{self, Error},
     plot::{plot_maker, ScalingPlot},
-    sensors::{device_type, search_sensors, SensorLabel},
+    selector::{Candidate, Selection, Selector},
+    sensors::{
+        device_type, thresholds, ChannelKind, DeviceKind, SensorLabel, SensorSource, TripPoint,
+    },
     Config,
 };
 use crossterm::{
@@ -16,7 +20,7 @@ use std::{
     fs::{self, File},
     io::{self, Write},
     path::PathBuf,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 pub struct SessionFile {
@@ -24,6 +28,15 @@ pub struct SessionFile {
     pub file: File,
     pub buffer: Vec<String>,
     pub flush_interval: usize,
+    pub precision: usize,
+    pub energy: Vec<(String, f64)>,
+    /// Throttling events summed over the session, per channel.
+    pub throttles: Vec<(String, u64)>,
+    pub last_frame: Option<Instant>,
+    /// Time spent in `read_sensors`, summed and worst case over `frames`.
+    pub sample_total: Duration,
+    pub sample_max: Duration,
+    pub frames: u32,
 }
 
 pub fn list_sessions() -> io::Result<Vec<(u16, PathBuf)>> {
@@ -39,7 +52,7 @@ pub fn list_sessions() -> io::Result<Vec<(u16, PathBuf)>> {
 
     for entry in entries.filter_map(|r| r.ok()) {
         let path = entry.path();
-        if path.extension().map_or(false, |ext| ext == "csv") {
+        if path.extension().is_some_and(|ext| ext == "csv") {
             if let Some(name) = path.file_stem() {
                 let name = name.to_string_lossy();
                 if let Some(num) = name.strip_prefix("session_") {
@@ -55,7 +68,12 @@ pub fn list_sessions() -> io::Result<Vec<(u16, PathBuf)>> {
     Ok(sessions)
 }
 
-fn session_writer(delay: u64) -> io::Result<SessionFile> {
+fn session_writer(
+    delay: u64,
+    precision: usize,
+    sensors: &[SensorLabel],
+    trips: &[TripPoint],
+) -> io::Result<SessionFile> {
     let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
     let session_dir = home.join("Documents").join("Twatch").join("session");
     fs::create_dir_all(&session_dir)?;
@@ -66,18 +84,95 @@ fn session_writer(delay: u64) -> io::Result<SessionFile> {
         if !candidate.exists() {
             let mut file = File::create(&candidate)?;
             writeln!(file, "# Delay:{}", delay)?;
-            writeln!(file, "Type,Label,Temp")?;
+            write_sensor_header(&mut file, sensors, precision)?;
+            for trip in trips {
+                writeln!(
+                    file,
+                    "# Trip:{},{},{:.*}",
+                    trip.zone, trip.kind, precision, trip.temp
+                )?;
+            }
+            writeln!(file, "Type,ID,Value")?;
             return Ok(SessionFile {
                 id: session_id,
                 file,
                 buffer: Vec::with_capacity(50),
                 flush_interval: 50,
+                precision,
+                energy: Vec::new(),
+                throttles: Vec::new(),
+                last_frame: None,
+                sample_total: Duration::ZERO,
+                sample_max: Duration::ZERO,
+                frames: 0,
             });
         }
         session_id += 1;
     }
 }
 
+fn write_sensor_header(
+    file: &mut File,
+    sensors: &[SensorLabel],
+    precision: usize,
+) -> io::Result<()> {
+    for sensor in sensors {
+        let d_type = device_type(sensor);
+        writeln!(file, "# Sensor:{},{},{}", d_type, sensor.id, sensor.label)?;
+        if let Some(calibrated) = &sensor.calibration {
+            let mut fields = vec![
+                format!("scale={}", calibrated.scale),
+                format!("offset={}", calibrated.offset),
+            ];
+            if let Some(driver_label) = &calibrated.driver_label {
+                fields.push(format!("driver_label={}", driver_label));
+            }
+            writeln!(
+                file,
+                "# Calibration:{},{},{}",
+                d_type,
+                sensor.id,
+                fields.join(",")
+            )?;
+        }
+        if let Some(placement) = &sensor.placement {
+            writeln!(
+                file,
+                "# Topology:{},{},{}",
+                d_type,
+                sensor.id,
+                placement.header_fields()
+            )?;
+        }
+        if let Some(identity) = &sensor.identity {
+            writeln!(
+                file,
+                "# Device:{},{},{}",
+                d_type,
+                sensor.id,
+                identity.header_fields()
+            )?;
+        }
+
+        // Only the static limits: alarm changes are recorded as events.
+        let decimals = sensor.kind.decimals(precision);
+        let fields: Vec<String> = thresholds(sensor)
+            .into_iter()
+            .map(|(name, value)| format!("{}={:.*}", name, decimals, value))
+            .collect();
+        if !fields.is_empty() {
+            writeln!(
+                file,
+                "# Threshold:{},{},{}",
+                d_type,
+                sensor.id,
+                fields.join(",")
+            )?;
+        }
+    }
+    Ok(())
+}
+
 fn flush_buffer(session: &mut SessionFile) -> io::Result<()> {
     for line in &session.buffer {
         writeln!(session.file, "{}", line)?;
@@ -87,15 +182,51 @@ fn flush_buffer(session: &mut SessionFile) -> io::Result<()> {
     Ok(())
 }
 
-fn record_frame(session: &mut SessionFile, sensors: &[SensorLabel]) -> io::Result<()> {
-    for sensor in sensors {
-        let d_type = device_type(sensor);
-        if d_type == "Unknown" {
+fn record_frame(
+    session: &mut SessionFile,
+    sensors: &[SensorLabel],
+    sample_time: Duration,
+) -> io::Result<()> {
+    session.sample_total += sample_time;
+    session.sample_max = session.sample_max.max(sample_time);
+    session.frames += 1;
+
+    let now = Instant::now();
+    let interval = session
+        .last_frame
+        .replace(now)
+        .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+    for sensor in sensors.iter().filter(|s| s.kind == ChannelKind::Power) {
+        let Some(watts) = sensor.value else {
             continue;
+        };
+        match session.energy.iter_mut().find(|(id, _)| *id == sensor.id) {
+            Some((_, joules)) => *joules += watts * interval,
+            None => session.energy.push((sensor.id.clone(), watts * interval)),
         }
+    }
+    for sensor in sensors.iter().filter(|s| s.kind == ChannelKind::Throttle) {
+        let events = sensor.value.unwrap_or_default() as u64;
+        match session
+            .throttles
+            .iter_mut()
+            .find(|(id, _)| *id == sensor.id)
+        {
+            Some((_, total)) => *total += events,
+            None => session.throttles.push((sensor.id.clone(), events)),
+        }
+    }
+
+    for sensor in sensors {
+        let d_type = device_type(sensor);
+        // Missing samples keep their row with an empty value.
+        let value = sensor
+            .value
+            .map(|value| format!("{:.*}", sensor.kind.decimals(session.precision), value))
+            .unwrap_or_default();
         session
             .buffer
-            .push(format!("{},{},{}", d_type, sensor.label, sensor.temp));
+            .push(format!("{},{},{}", d_type, sensor.id, value));
     }
     if session.buffer.len() >= session.flush_interval {
         flush_buffer(session)?;
@@ -103,33 +234,384 @@ fn record_frame(session: &mut SessionFile, sensors: &[SensorLabel]) -> io::Resul
     Ok(())
 }
 
-fn format_json_frame(sensors: &[SensorLabel], elapsed: u16) -> String {
-    let mut parts = vec![format!("\"elapsed\":{}", elapsed)];
+fn finish_session(session: &mut SessionFile, total_start: Instant, target: f64) -> io::Result<()> {
+    flush_buffer(session)?;
+    writeln!(
+        session.file,
+        "#Total: {:.3}",
+        total_start.elapsed().as_secs_f64()
+    )?;
+    for (id, joules) in &session.energy {
+        writeln!(session.file, "#Energy:POWER,{},{:.3}", id, joules)?;
+    }
+    for (id, events) in &session.throttles {
+        writeln!(session.file, "#Throttle:THROTTLE,{},{}", id, events)?;
+    }
+    if session.frames > 0 {
+        writeln!(
+            session.file,
+            "#Latency: mean_us={},max_us={},frames={}",
+            session.sample_total.as_micros() / u128::from(session.frames),
+            session.sample_max.as_micros(),
+            session.frames
+        )?;
+    }
+    writeln!(session.file, "CPU,Exit,{:.*}", session.precision, target)?;
+    Ok(())
+}
+
+/// Every channel seen during a session, in first-seen order, so rows keep
+/// their place when a device disappears and their CSV series stay aligned.
+struct Roster {
+    /// Last reading of each channel, kept for its offline rows.
+    known: Vec<SensorLabel>,
+    /// IDs missing from the latest frame.
+    offline: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Hotplug {
+    Appeared,
+    Disappeared,
+    /// An AC adapter came online.
+    Plugged,
+    /// An AC adapter went offline: the machine is now on battery.
+    Unplugged,
+    /// A channel's alarm flag was raised.
+    Alarm,
+    /// A channel's alarm flag was cleared.
+    AlarmCleared,
+}
+
+impl Hotplug {
+    fn name(self) -> &'static str {
+        match self {
+            Hotplug::Appeared => "appeared",
+            Hotplug::Disappeared => "disappeared",
+            Hotplug::Plugged => "plugged",
+            Hotplug::Unplugged => "unplugged",
+            Hotplug::Alarm => "alarm",
+            Hotplug::AlarmCleared => "alarm_cleared",
+        }
+    }
+}
+
+impl Roster {
+    fn new(sensors: &[SensorLabel]) -> Self {
+        Roster {
+            // Without a previous alarm state, an alarm already raised is
+            // recorded in the first frame.
+            known: sensors
+                .iter()
+                .map(|sensor| SensorLabel {
+                    alarm: None,
+                    ..sensor.clone()
+                })
+                .collect(),
+            offline: Vec::new(),
+        }
+    }
+
+    /// Lines a frame up with the roster: new channels are appended and
+    /// missing ones keep their row without a value. Returns the aligned
+    /// frame, the channels seen for the first time, and what changed,
+    /// including AC adapters going on or off line and alarms raised or
+    /// cleared.
+    fn update(
+        &mut self,
+        sensors: Vec<SensorLabel>,
+    ) -> (Vec<SensorLabel>, Vec<SensorLabel>, Vec<(Hotplug, String)>) {
+        let mut events = Vec::new();
+        let mut new = Vec::new();
+        for sensor in &sensors {
+            if !self.known.iter().any(|k| k.id == sensor.id) {
+                events.push((Hotplug::Appeared, sensor.id.clone()));
+                new.push(sensor.clone());
+                self.known.push(SensorLabel {
+                    alarm: None,
+                    ..sensor.clone()
+                });
+            }
+        }
+
+        let mut frame = Vec::with_capacity(self.known.len());
+        for known in &mut self.known {
+            let was_offline = self.offline.contains(&known.id);
+            match sensors.iter().find(|s| s.id == known.id) {
+                Some(sensor) => {
+                    if was_offline {
+                        self.offline.retain(|id| *id != known.id);
+                        events.push((Hotplug::Appeared, known.id.clone()));
+                    }
+                    if sensor.kind == ChannelKind::State {
+                        if let (Some(before), Some(now)) = (known.value, sensor.value) {
+                            if (before != 0.0) != (now != 0.0) {
+                                let event = if now != 0.0 {
+                                    Hotplug::Plugged
+                                } else {
+                                    Hotplug::Unplugged
+                                };
+                                events.push((event, known.id.clone()));
+                            }
+                        }
+                    }
+                    let alarm = sensor.alarm.unwrap_or(false);
+                    if alarm != known.alarm.unwrap_or(false) && sensor.alarm.is_some() {
+                        let event = if alarm {
+                            Hotplug::Alarm
+                        } else {
+                            Hotplug::AlarmCleared
+                        };
+                        events.push((event, known.id.clone()));
+                    }
+                    *known = sensor.clone();
+                    frame.push(sensor.clone());
+                }
+                None => {
+                    if !was_offline {
+                        self.offline.push(known.id.clone());
+                        events.push((Hotplug::Disappeared, known.id.clone()));
+                    }
+                    frame.push(SensorLabel {
+                        value: None,
+                        alarm: None,
+                        ..known.clone()
+                    });
+                }
+            }
+        }
+        (frame, new, events)
+    }
+}
+
+/// Writes hotplug events, and the header lines of channels new to the
+/// session, ahead of the frame they were first seen in.
+fn record_hotplug(
+    session: &mut SessionFile,
+    new: &[SensorLabel],
+    events: &[(Hotplug, String)],
+) -> io::Result<()> {
+    flush_buffer(session)?;
+    write_sensor_header(&mut session.file, new, session.precision)?;
+    for (event, id) in events {
+        writeln!(
+            session.file,
+            "# Event:{},{},{}",
+            session.frames,
+            event.name(),
+            id
+        )?;
+    }
+    Ok(())
+}
+
+fn format_json_frame(
+    sensors: &[SensorLabel],
+    offline: &[String],
+    elapsed: u16,
+    sample_time: Duration,
+    precision: usize,
+) -> String {
+    let mut parts = vec![
+        format!("\"elapsed\":{}", elapsed),
+        format!("\"sample_us\":{}", sample_time.as_micros()),
+    ];
     for s in sensors {
-        let d_type = device_type(s);
-        if d_type == "Unknown" {
-            continue;
+        let key = &s.id;
+        let decimals = s.kind.decimals(precision);
+        match s.value {
+            Some(value) => parts.push(format!("\"{}\":{:.*}", key, decimals, value)),
+            None => parts.push(format!("\"{}\":null", key)),
+        }
+        for (name, value) in thresholds(s) {
+            parts.push(format!("\"{}_{}\":{:.*}", key, name, decimals, value));
+        }
+        if let Some(alarm) = s.alarm {
+            parts.push(format!("\"{}_alarm\":{}", key, alarm));
+        }
+        if offline.contains(key) {
+            parts.push(format!("\"{}_offline\":true", key));
         }
-        let key = format!("{}_{}", d_type.to_lowercase(), s.label.to_lowercase());
-        parts.push(format!("\"{}\":{}", key, s.temp));
     }
     format!("{{{}}}", parts.join(", "))
 }
 
-fn target_temp(sensors: &[SensorLabel], sensor_kind: &str) -> u32 {
-    match sensor_kind {
-        "gpu" => sensors.iter().find(|s| s.is_amd_gpu).map(|s| s.temp).unwrap_or(0),
-        "nvme" => sensors.iter().find(|s| s.is_nvme).map(|s| s.temp).unwrap_or(0),
-        _ => sensors.iter().find(|s| s.is_cpu).map(|s| s.temp).unwrap_or(0),
+/// Channel that drives `--by-temperature` and `temp`: the first
+/// temperature the trigger selects (`--trigger gpu` selects several
+/// kinds), or without a trigger the first CPU temperature among the
+/// selected channels, else the first temperature. A trigger that selects
+/// no temperature is an error.
+pub fn target_sensor<'a>(
+    sensors: &'a [SensorLabel],
+    trigger: Option<&Selector>,
+) -> error::Result<Option<&'a SensorLabel>> {
+    if let Some(trigger) = trigger {
+        let matching = sensors
+            .iter()
+            .filter(|s| trigger.matches(&Candidate::from(*s)));
+        if matching.clone().next().is_none() {
+            return Err(Error::Selector(format!(
+                "'{}' given as --trigger matches no sensor",
+                trigger
+            )));
+        }
+        let mut temperatures = matching.filter(|s| s.kind == ChannelKind::Temperature);
+        let Some(first) = temperatures.clone().next() else {
+            return Err(Error::Selector(format!(
+                "'{}' given as --trigger matches no temperature sensor",
+                trigger
+            )));
+        };
+        return Ok(temperatures.find(|s| s.value.is_some()).or(Some(first)));
+    }
+
+    let mut temperatures = sensors
+        .iter()
+        .filter(|s| s.kind == ChannelKind::Temperature && s.value.is_some());
+    Ok(temperatures
+        .clone()
+        .find(|s| s.device == DeviceKind::Cpu)
+        .or_else(|| temperatures.next()))
+}
+
+/// Hottest per-core or per-CCD temperature, for naming the hot spot.
+fn hot_spot(sensors: &[SensorLabel]) -> Option<&SensorLabel> {
+    sensors
+        .iter()
+        .filter(|s| s.kind == ChannelKind::Temperature && s.value.is_some())
+        .filter(|s| s.placement.as_ref().is_some_and(|p| p.is_local()))
+        .max_by(|a, b| {
+            a.value
+                .unwrap_or_default()
+                .total_cmp(&b.value.unwrap_or_default())
+        })
+}
+
+/// Thermal throttling events the hardware reported in this frame.
+fn throttle_events(sensors: &[SensorLabel]) -> u64 {
+    sensors
+        .iter()
+        .filter(|s| s.kind == ChannelKind::Throttle)
+        .filter_map(|s| s.value)
+        .sum::<f64>() as u64
+}
+
+/// Whether any AC adapter is online; `None` on machines without one.
+fn on_ac_power(sensors: &[SensorLabel]) -> Option<bool> {
+    let mut adapters = sensors
+        .iter()
+        .filter(|s| s.kind == ChannelKind::State)
+        .filter_map(|s| s.value)
+        .peekable();
+    adapters.peek()?;
+    Some(adapters.any(|value| value != 0.0))
+}
+
+/// Smallest full-scale value for a gauge when the channel reports no max.
+fn gauge_floor(kind: ChannelKind) -> f64 {
+    match kind {
+        ChannelKind::Temperature | ChannelKind::Power => 100.0,
+        ChannelKind::Fan => 2000.0,
+        ChannelKind::Voltage => 12.0,
+        ChannelKind::Current => 10.0,
+        ChannelKind::Cooling => 1.0,
+        ChannelKind::Frequency => 1000.0,
+        ChannelKind::Utilization => 100.0,
+        ChannelKind::Load => 1.0,
+        ChannelKind::Pwm => 100.0,
+        ChannelKind::State => 1.0,
+        ChannelKind::Capacity => 100.0,
+        ChannelKind::Throttle => 1.0,
+        ChannelKind::Pressure => 100.0,
+    }
+}
+
+/// How full a gauge is. A zero or unknown scale (a max of 0 from the
+/// driver) leaves it empty; ratatui panics on a ratio outside 0..=1.
+fn gauge_ratio(value: f64, scale: f64) -> f64 {
+    let ratio = value / scale;
+    if scale <= 0.0 || !ratio.is_finite() {
+        return 0.0;
     }
+    ratio.clamp(0.0, 1.0)
 }
 
-fn draw_live_frame(frame: &mut Frame, sensors: &[SensorLabel], status: &str, subtitle: &str) {
+/// Per-core and per-GPU channels drawn as one-line gauges beside the sensor
+/// gauges.
+fn compact_section(sensor: &SensorLabel) -> Option<&'static str> {
+    match sensor.kind {
+        ChannelKind::Frequency | ChannelKind::Utilization if sensor.device == DeviceKind::Gpu => {
+            Some(" GPU ")
+        }
+        ChannelKind::Frequency => Some(" Frequency "),
+        ChannelKind::Utilization | ChannelKind::Load | ChannelKind::Pressure => Some(" Load "),
+        ChannelKind::Throttle => Some(" Throttle "),
+        _ => None,
+    }
+}
+
+fn draw_compact_section(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    sensors: &[&SensorLabel],
+    offline: &[String],
+    precision: usize,
+) {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            (0..sensors.len())
+                .map(|_| Constraint::Length(1))
+                .collect::<Vec<_>>(),
+        )
+        .split(inner);
+
+    for (sensor, row) in sensors.iter().zip(rows.iter()) {
+        let value = sensor.value.unwrap_or_default();
+        let scale = sensor
+            .max
+            .unwrap_or_else(|| gauge_floor(sensor.kind).max(value));
+        let shown = if offline.contains(&sensor.id) {
+            "offline".to_string()
+        } else {
+            sensor.display_value(precision)
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(gauge_ratio(value, scale))
+            .label(format!("{} {}", sensor.label, shown));
+        frame.render_widget(gauge, *row);
+    }
+}
+
+fn draw_live_frame(
+    frame: &mut Frame,
+    sensors: &[SensorLabel],
+    offline: &[String],
+    precision: usize,
+    status: &str,
+    subtitle: &str,
+    throttling: bool,
+) {
     let area = frame.area();
 
+    // Red while the CPU reports throttling in this frame.
+    let (title, header_style) = if throttling {
+        (
+            " Twatch — THROTTLING ",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )
+    } else {
+        (" Twatch ", Style::default().fg(Color::Cyan))
+    };
     let header = Paragraph::new(status)
-        .block(Block::default().borders(Borders::ALL).title(" Twatch "))
-        .style(Style::default().fg(Color::Cyan));
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(header_style);
 
     let header_height = 3;
     let footer_height = 1;
@@ -145,14 +627,46 @@ fn draw_live_frame(frame: &mut Frame, sensors: &[SensorLabel], status: &str, sub
 
     frame.render_widget(header, layout[0]);
 
-    let sensor_count = sensors
-        .iter()
-        .filter(|s| device_type(s) != "Unknown")
-        .count();
+    let (mut gauge_sensors, compact_sensors): (Vec<&SensorLabel>, Vec<&SensorLabel>) =
+        sensors.iter().partition(|s| compact_section(s).is_none());
+    // Keep readings of one package together, ordered by CCD and core.
+    gauge_sensors.sort_by_key(|s| s.placement.as_ref().map(|p| p.sort_key()));
+
+    let mut sections: Vec<(&str, Vec<&SensorLabel>)> = Vec::new();
+    for sensor in compact_sensors {
+        let title = compact_section(sensor).unwrap_or_default();
+        match sections.iter_mut().find(|(t, _)| *t == title) {
+            Some((_, members)) => members.push(sensor),
+            None => sections.push((title, vec![sensor])),
+        }
+    }
+
+    let body = if sections.is_empty() {
+        layout[1]
+    } else {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(30)])
+            .split(layout[1]);
+        let section_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                sections
+                    .iter()
+                    .map(|(_, members)| Constraint::Length(members.len() as u16 + 2))
+                    .collect::<Vec<_>>(),
+            )
+            .split(columns[1]);
+        for ((title, members), section_area) in sections.iter().zip(section_areas.iter()) {
+            draw_compact_section(frame, *section_area, title, members, offline, precision);
+        }
+        columns[0]
+    };
+
+    let sensor_count = gauge_sensors.len();
     if sensor_count == 0 {
-        let msg =
-            Paragraph::new("No sensors found").block(Block::default().borders(Borders::ALL));
-        frame.render_widget(msg, layout[1]);
+        let msg = Paragraph::new("No sensors found").block(Block::default().borders(Borders::ALL));
+        frame.render_widget(msg, body);
         return;
     }
 
@@ -163,27 +677,64 @@ fn draw_live_frame(frame: &mut Frame, sensors: &[SensorLabel], status: &str, sub
                 .map(|_| Constraint::Length(3))
                 .collect::<Vec<_>>(),
         )
-        .split(layout[1]);
+        .split(body);
 
-    let max_temp: u32 = sensors.iter().map(|s| s.temp).max().unwrap_or(100).max(100);
+    let kind_scale = |kind: ChannelKind, floor: f64| {
+        sensors
+            .iter()
+            .filter(|s| s.kind == kind)
+            .filter_map(|s| s.value)
+            .fold(floor, f64::max)
+    };
+    let max_temp = kind_scale(ChannelKind::Temperature, 100.0);
 
-    let mut row = 0;
-    for sensor in sensors {
+    for (row, sensor) in gauge_sensors.into_iter().enumerate() {
         let d_type = device_type(sensor);
-        if d_type == "Unknown" || row >= sensor_layout.len() {
-            continue;
-        }
 
-        let ratio = (sensor.temp as f64 / max_temp as f64).min(1.0);
-        let color = if sensor.temp >= 70 {
-            Color::Red
-        } else if sensor.temp >= 50 {
-            Color::Yellow
-        } else {
-            Color::Green
+        let scale = match sensor.kind {
+            ChannelKind::Temperature => max_temp,
+            kind => sensor
+                .max
+                .unwrap_or_else(|| kind_scale(kind, gauge_floor(kind))),
+        };
+        let value = sensor.value.unwrap_or_default();
+        let ratio = gauge_ratio(value, scale);
+        let color = match sensor.kind {
+            _ if sensor.value.is_none() => Color::DarkGray,
+            ChannelKind::Fan => Color::Blue,
+            ChannelKind::Pwm => Color::LightBlue,
+            ChannelKind::Power => Color::Magenta,
+            ChannelKind::Voltage => Color::LightCyan,
+            ChannelKind::Current => Color::LightMagenta,
+            ChannelKind::Cooling if value > 0.0 => Color::LightRed,
+            ChannelKind::Cooling => Color::DarkGray,
+            ChannelKind::Frequency | ChannelKind::Utilization | ChannelKind::Load => Color::Cyan,
+            ChannelKind::Temperature if value >= 70.0 => Color::Red,
+            ChannelKind::Temperature if value >= 50.0 => Color::Yellow,
+            ChannelKind::Temperature => Color::Green,
+            ChannelKind::State if value > 0.0 => Color::Green,
+            ChannelKind::State => Color::Yellow,
+            ChannelKind::Capacity if value <= 20.0 => Color::Red,
+            ChannelKind::Capacity => Color::Green,
+            ChannelKind::Throttle if value > 0.0 => Color::Red,
+            ChannelKind::Throttle => Color::DarkGray,
+            ChannelKind::Pressure => Color::Cyan,
         };
 
-        let label = format!("[{}] {}", d_type, sensor.label);
+        let mut label = format!("[{}] {}  {}", d_type, sensor.label, sensor.id);
+        if let Some(placement) = &sensor.placement {
+            label.push_str(&format!("  ({})", placement.describe()));
+        }
+        if let Some(identity) = &sensor.identity {
+            label.push_str(&format!("  ({})", identity.describe()));
+        }
+        if sensor.alarm == Some(true) {
+            label.push_str(" [ALARM]");
+        }
+        let is_offline = offline.contains(&sensor.id);
+        if is_offline {
+            label.push_str(" [OFFLINE]");
+        }
         let gauge = Gauge::default()
             .block(Block::default().borders(Borders::NONE))
             .gauge_style(Style::default().fg(color))
@@ -192,21 +743,23 @@ fn draw_live_frame(frame: &mut Frame, sensors: &[SensorLabel], status: &str, sub
 
         frame.render_widget(gauge, sensor_layout[row]);
 
-        let value = format!("{}°C", sensor.temp);
+        let value = if is_offline {
+            "offline".to_string()
+        } else {
+            sensor.display_value(precision)
+        };
         let value_area = Rect {
             x: sensor_layout[row]
                 .x
-                .saturating_add(sensor_layout[row].width.saturating_sub(10)),
+                .saturating_add(sensor_layout[row].width.saturating_sub(12)),
             y: sensor_layout[row].y.saturating_add(1),
-            width: 8,
+            width: 10,
             height: 1,
         };
         frame.render_widget(
             Paragraph::new(value).style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
             value_area,
         );
-
-        row += 1;
     }
 
     let footer = Paragraph::new(subtitle)
@@ -215,78 +768,142 @@ fn draw_live_frame(frame: &mut Frame, sensors: &[SensorLabel], status: &str, sub
     frame.render_widget(footer, layout[2]);
 }
 
+pub struct RunOptions {
+    pub by_temperature: bool,
+    pub capture_limit: u16,
+    pub initial_temp: f64,
+    pub end_temp: f64,
+    pub json_output: bool,
+}
+
 pub fn run_session(
     config: &Config,
-    by_temperature: bool,
-    capture_limit: u16,
-    initial_temp: u32,
-    end_temp: u32,
-    sensor_kind: &str,
-    json_output: bool,
-) -> io::Result<()> {
+    source: &mut dyn SensorSource,
+    options: RunOptions,
+) -> error::Result<()> {
+    let RunOptions {
+        by_temperature,
+        capture_limit,
+        initial_temp,
+        end_temp,
+        json_output,
+    } = options;
     let ms_delay = config.delay;
 
+    // Discover sensors before taking over the terminal, so failures print
+    // to a normal screen.
+    let precision = config.precision;
+    let discovered = source.read_sensors()?;
+    target_sensor(&discovered, config.trigger.as_ref())?;
+    let mut session = session_writer(ms_delay, precision, &discovered, &source.trip_points())
+        .map_err(Error::Session)?;
+    let mut roster = Roster::new(&discovered);
+
     if !json_output {
-        enable_raw_mode()?;
+        enable_raw_mode().map_err(Error::Terminal)?;
     }
     let mut stdout = io::stdout();
     if !json_output {
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen).map_err(Error::Terminal)?;
     }
 
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(backend).map_err(Error::Terminal)?;
 
-    let mut session = session_writer(ms_delay)?;
     let session_id = session.id;
     let mut elapsed = 0u16;
     let total_start = Instant::now();
+    let mut throttled = 0u64;
 
-    let result = (|| -> io::Result<bool> {
+    let result = (|| -> error::Result<bool> {
         loop {
-            let sensors = search_sensors()?;
-            record_frame(&mut session, &sensors)?;
-            let target = target_temp(&sensors, sensor_kind);
+            let sample_start = Instant::now();
+            let sensors = source.read_sensors()?;
+            let sample_time = sample_start.elapsed();
+            let (sensors, new, events) = roster.update(sensors);
+            if !events.is_empty() {
+                record_hotplug(&mut session, &new, &events).map_err(Error::Session)?;
+            }
+            record_frame(&mut session, &sensors, sample_time).map_err(Error::Session)?;
+            let target_sensor = target_sensor(&sensors, config.trigger.as_ref())?;
+            let target = target_sensor.and_then(|s| s.value).unwrap_or(0.0);
+            let throttling = throttle_events(&sensors);
+            throttled += throttling;
 
             if json_output {
-                println!("{}", format_json_frame(&sensors, elapsed));
+                println!(
+                    "{}",
+                    format_json_frame(&sensors, &roster.offline, elapsed, sample_time, precision)
+                );
             } else {
-                let sensor_label = match sensor_kind {
-                    "gpu" => "GPU",
-                    "nvme" => "NVMe",
-                    _ => "CPU",
-                };
+                let sensor_label = target_sensor
+                    .map(|s| format!("{} {}", device_type(s), s.label))
+                    .unwrap_or_else(|| "none".to_string());
 
-                let status = if by_temperature {
+                let mut status = if by_temperature {
                     format!(
-                        "Temp Trigger [{}]  |  T: {}°C  |  Range: [{}, {}]°C",
-                        sensor_label, target, initial_temp, end_temp
+                        "Temp Trigger [{}]  |  T: {:.*}°C  |  Range: [{}, {}]°C",
+                        sensor_label, precision, target, initial_temp, end_temp
                     )
                 } else {
                     format!(
-                        "Capture Limit  |  {}/{}  |  T: {}°C",
-                        elapsed, capture_limit, target
+                        "Capture Limit  |  {}/{}  |  T: {:.*}°C",
+                        elapsed, capture_limit, precision, target
                     )
                 };
+                if throttled > 0 {
+                    status.push_str(&format!("  |  Throttle events: {}", throttled));
+                }
+
+                let mut subtitle = format!(
+                    "Sample: {}µs  |  Delay: {}ms  |  Session {}  |  q=quit",
+                    sample_time.as_micros(),
+                    ms_delay,
+                    session_id
+                );
+                if let Some(hot) = hot_spot(&sensors) {
+                    let placement = hot.placement.as_ref().map(|p| p.describe());
+                    subtitle = format!(
+                        "Hot spot: {} {} ({})  |  {}",
+                        hot.label,
+                        hot.display_value(precision),
+                        placement.unwrap_or_default(),
+                        subtitle
+                    );
+                }
+
+                if let Some(on_ac) = on_ac_power(&sensors) {
+                    let source = if on_ac { "AC" } else { "battery" };
+                    subtitle = format!("Power: {}  |  {}", source, subtitle);
+                }
 
-                let subtitle = format!("Delay: {}ms  |  Session {}  |  q=quit", ms_delay, session_id);
+                if !roster.offline.is_empty() {
+                    subtitle = format!("Offline: {}  |  {}", roster.offline.len(), subtitle);
+                }
 
                 terminal
-                    .draw(|f| draw_live_frame(f, &sensors, &status, &subtitle))
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                    .draw(|f| {
+                        draw_live_frame(
+                            f,
+                            &sensors,
+                            &roster.offline,
+                            precision,
+                            &status,
+                            &subtitle,
+                            throttling > 0,
+                        )
+                    })
+                    .map_err(Error::Terminal)?;
             }
 
-            if !json_output {
-                if event::poll(std::time::Duration::from_millis(ms_delay / 4))
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-                {
-                    if let Event::Key(key) =
-                        event::read().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
-                    {
-                        if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                            let _ = flush_buffer(&mut session);
-                            return Ok(false);
-                        }
+            if !json_output
+                && event::poll(std::time::Duration::from_millis(ms_delay / 4))
+                    .map_err(Error::Terminal)?
+            {
+                if let Event::Key(key) = event::read().map_err(Error::Terminal)? {
+                    if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
+                        let _ = flush_buffer(&mut session);
+                        return Ok(false);
                     }
                 }
             }
@@ -294,18 +911,14 @@ pub fn run_session(
             std::thread::sleep(std::time::Duration::from_millis(ms_delay * 3 / 4));
 
             if by_temperature && target >= end_temp {
-                flush_buffer(&mut session)?;
-                writeln!(session.file, "#Total: {:.3}", total_start.elapsed().as_secs())?;
-                writeln!(session.file, "CPU,Exit,{}", target)?;
+                finish_session(&mut session, total_start, target).map_err(Error::Session)?;
                 return Ok(true);
             }
 
             if !by_temperature {
                 elapsed += 1;
                 if elapsed >= capture_limit {
-                    flush_buffer(&mut session)?;
-                    writeln!(session.file, "#Total: {:.3}", total_start.elapsed().as_secs())?;
-                    writeln!(session.file, "CPU,Exit,{}", target)?;
+                    finish_session(&mut session, total_start, target).map_err(Error::Session)?;
                     return Ok(true);
                 }
             }
@@ -313,8 +926,8 @@ pub fn run_session(
     })();
 
     if !json_output {
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        disable_raw_mode().map_err(Error::Terminal)?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen).map_err(Error::Terminal)?;
         terminal.show_cursor().ok();
     }
 
@@ -325,8 +938,218 @@ pub fn run_session(
             max_plot_temperature: config.max_plot_temp,
             number_of_steps_for_graph: config.temp_steps,
         };
-        plot_maker(&[session_id], scale);
+        // The session only holds selected channels already.
+        plot_maker(&[session_id], scale, &Selection::default())?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{draw_live_frame, gauge_floor, gauge_ratio, target_sensor, Hotplug, Roster};
+    use crate::sensors::{ChannelKind, DeviceKind, SensorLabel};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    const KINDS: &[&str] = &[
+        "temp", "fan", "power", "volt", "curr", "cooling", "freq", "util", "load", "pwm", "state",
+        "capacity", "throttle", "pressure",
+    ];
+
+    #[test]
+    fn gauge_floors_are_positive() {
+        for name in KINDS {
+            let kind = ChannelKind::from_name(name).unwrap();
+            assert!(gauge_floor(kind) > 0.0, "{}", name);
+        }
+    }
+
+    #[test]
+    fn gauge_ratios_stay_in_range() {
+        assert_eq!(gauge_ratio(6.0, 12.0), 0.5);
+        assert_eq!(gauge_ratio(-1.0, 12.0), 0.0);
+        assert_eq!(gauge_ratio(30.0, 12.0), 1.0);
+        assert_eq!(gauge_ratio(0.0, 0.0), 0.0);
+        assert_eq!(gauge_ratio(3.0, 0.0), 0.0);
+        assert_eq!(gauge_ratio(3.0, -5.0), 0.0);
+        assert_eq!(gauge_ratio(f64::NAN, 12.0), 0.0);
+    }
+
+    #[test]
+    fn draws_zero_scale_gauges() {
+        let channel = |id: &str, kind, max| SensorLabel {
+            id: id.to_string(),
+            label: id.to_string(),
+            chip: "nct6798".to_string(),
+            kind,
+            device: DeviceKind::Board,
+            value: Some(0.0),
+            max,
+            ..Default::default()
+        };
+        // A rail reading 0 V without a max, and a cooling device whose
+        // driver reports max_state 0.
+        let sensors = [
+            channel("nct6798/nct6775.656/in3", ChannelKind::Voltage, None),
+            channel(
+                "acpitz/cooling_device0/state",
+                ChannelKind::Cooling,
+                Some(0.0),
+            ),
+        ];
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal
+            .draw(|frame| draw_live_frame(frame, &sensors, &[], 1, "", "", false))
+            .unwrap();
+    }
+
+    fn reading(id: &str, kind: ChannelKind, value: f64) -> SensorLabel {
+        SensorLabel {
+            id: id.to_string(),
+            label: id.to_string(),
+            chip: id.split('/').next().unwrap().to_string(),
+            kind,
+            device: DeviceKind::Board,
+            value: Some(value),
+            ..Default::default()
+        }
+    }
+
+    fn names(events: &[(Hotplug, String)]) -> Vec<(&'static str, &str)> {
+        events
+            .iter()
+            .map(|(event, id)| (event.name(), id.as_str()))
+            .collect()
+    }
+
+    fn ids(frame: &[SensorLabel]) -> Vec<&str> {
+        frame.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    const CPU: &str = "k10temp/pci-00c3/temp1";
+    const DISK: &str = "nvme/nvme0/temp1";
+    const FAN: &str = "nct6798/nct6775.656/fan2";
+
+    #[test]
+    fn tracks_channels_that_come_and_go() {
+        let temp = ChannelKind::Temperature;
+        let mut roster = Roster::new(&[reading(CPU, temp, 50.0), reading(DISK, temp, 40.0)]);
+
+        let (frame, new, events) =
+            roster.update(vec![reading(CPU, temp, 51.0), reading(DISK, temp, 41.0)]);
+        assert_eq!(ids(&frame), [CPU, DISK]);
+        assert!(new.is_empty());
+        assert!(events.is_empty());
+
+        // A missing channel keeps its row without a value.
+        let (frame, new, events) = roster.update(vec![reading(CPU, temp, 52.0)]);
+        assert_eq!(ids(&frame), [CPU, DISK]);
+        assert_eq!(frame[1].value, None);
+        assert!(new.is_empty());
+        assert_eq!(names(&events), [("disappeared", DISK)]);
+        assert_eq!(roster.offline, [DISK]);
+
+        // Staying away is not reported again.
+        let (_, _, events) = roster.update(vec![reading(CPU, temp, 52.0)]);
+        assert!(events.is_empty());
+
+        // A new channel is appended, so earlier columns stay put.
+        let (frame, new, events) = roster.update(vec![
+            reading(FAN, ChannelKind::Fan, 900.0),
+            reading(CPU, temp, 53.0),
+        ]);
+        assert_eq!(ids(&frame), [CPU, DISK, FAN]);
+        assert_eq!(frame[0].value, Some(53.0));
+        assert_eq!(frame[2].value, Some(900.0));
+        assert_eq!(ids(&new), [FAN]);
+        assert_eq!(names(&events), [("appeared", FAN)]);
+
+        // Coming back reuses the old row and is not a new channel.
+        let (frame, new, events) = roster.update(vec![
+            reading(DISK, temp, 42.0),
+            reading(CPU, temp, 53.0),
+            reading(FAN, ChannelKind::Fan, 910.0),
+        ]);
+        assert_eq!(ids(&frame), [CPU, DISK, FAN]);
+        assert_eq!(frame[1].value, Some(42.0));
+        assert!(new.is_empty());
+        assert_eq!(names(&events), [("appeared", DISK)]);
+        assert!(roster.offline.is_empty());
+    }
+
+    #[test]
+    fn reports_ac_transitions() {
+        let ac = "ac/ac/online";
+        let mut roster = Roster::new(&[reading(ac, ChannelKind::State, 1.0)]);
+
+        let (_, _, events) = roster.update(vec![reading(ac, ChannelKind::State, 1.0)]);
+        assert!(events.is_empty());
+        let (_, _, events) = roster.update(vec![reading(ac, ChannelKind::State, 0.0)]);
+        assert_eq!(names(&events), [("unplugged", ac)]);
+        let (_, _, events) = roster.update(vec![reading(ac, ChannelKind::State, 0.0)]);
+        assert!(events.is_empty());
+        let (_, _, events) = roster.update(vec![reading(ac, ChannelKind::State, 1.0)]);
+        assert_eq!(names(&events), [("plugged", ac)]);
+
+        // A failed read is neither.
+        let mut failed = reading(ac, ChannelKind::State, 0.0);
+        failed.value = None;
+        let (_, _, events) = roster.update(vec![failed]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn reports_alarms_once() {
+        let mut raised = reading(CPU, ChannelKind::Temperature, 95.0);
+        raised.alarm = Some(true);
+        let mut cleared = reading(CPU, ChannelKind::Temperature, 60.0);
+        cleared.alarm = Some(false);
+
+        // An alarm already raised at startup is reported in the first frame.
+        let mut roster = Roster::new(&[raised.clone()]);
+        let (_, _, events) = roster.update(vec![raised.clone()]);
+        assert_eq!(names(&events), [("alarm", CPU)]);
+        let (_, _, events) = roster.update(vec![raised]);
+        assert!(events.is_empty());
+        let (_, _, events) = roster.update(vec![cleared]);
+        assert_eq!(names(&events), [("alarm_cleared", CPU)]);
+    }
+
+    #[test]
+    fn triggers_on_temperatures_only() {
+        let mut failed = reading("amdgpu/card0/temp1", ChannelKind::Temperature, 0.0);
+        failed.device = DeviceKind::Gpu;
+        failed.value = None;
+        let mut junction = reading("amdgpu/card0/temp2", ChannelKind::Temperature, 70.0);
+        junction.device = DeviceKind::Gpu;
+        let mut gpu_fan = reading("amdgpu/card0/fan1", ChannelKind::Fan, 1200.0);
+        gpu_fan.device = DeviceKind::Gpu;
+        let mut cpu = reading(CPU, ChannelKind::Temperature, 50.0);
+        cpu.device = DeviceKind::Cpu;
+        let sensors = [
+            reading(FAN, ChannelKind::Fan, 900.0),
+            gpu_fan,
+            failed,
+            junction,
+            cpu,
+        ];
+        let target = |trigger: Option<&str>| {
+            let trigger = trigger.map(|value| value.parse().unwrap());
+            target_sensor(&sensors, trigger.as_ref()).map(|s| s.map(|s| s.id.clone()))
+        };
+
+        assert_eq!(target(None).unwrap().as_deref(), Some(CPU));
+        assert_eq!(
+            target(Some("gpu")).unwrap().as_deref(),
+            Some("amdgpu/card0/temp2")
+        );
+        assert_eq!(
+            target(Some("id:amdgpu/card0/temp1")).unwrap().as_deref(),
+            Some("amdgpu/card0/temp1")
+        );
+        let error = target(Some("fan")).err().unwrap().to_string();
+        assert!(error.contains("no temperature sensor"), "{}", error);
+        let error = target(Some("id:acpitz/*")).err().unwrap().to_string();
+        assert!(error.contains("matches no sensor"), "{}", error);
+    }
+}